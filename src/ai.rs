@@ -5,7 +5,7 @@ use crate::axiom::{Effect, EffectType};
 pub struct AIPlugin;
 
 impl Plugin for AIPlugin {
-    fn build(&self, app: &mut App) {
+    fn build(&self, _app: &mut App) {
 
     }
 }
@@ -1,16 +1,17 @@
-use std::collections::HashMap;
+use std::{collections::{HashMap, VecDeque}, fmt, mem::{self, discriminant}};
 
-use bevy::ecs::entity::Entity;
+use bevy::{ecs::entity::Entity, prelude::{Event, Resource}};
+use serde::{Deserialize, Serialize};
 
-use crate::{soul::Soul, species::Species, map::{get_entity_at_coords, bresenham_line, is_in_bounds, xy_idx}, components::Faction};
+use crate::{soul::Soul, species::{match_species_with_priority, Species}, map::{get_entity_at_coords, get_neighbours, bresenham_line, idx_xy, is_in_bounds, xy_idx}, components::{Faction, Position, Virtues}};
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Effect {
     pub stacks: usize,
     pub effect_type: EffectType,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum EffectType {
     Glamour, // ++ casting, -- deal dmg // Your soul, a droplet, drowning in an ocean of endless lives.
     Pride, // ++ deal dmg, -- take dmg* // Us, standing on towers of gold so high and bright they burn away all doubt. You, so, so below, in a pit of submission so hidden one wonders how we even noticed your existence.
@@ -23,41 +24,73 @@ pub enum EffectType {
     Meltdown,
     OpenDoor,
     AssignedPatient {link: Entity},
+    Link {axiom: Box<(Form, Function)>, targets: Vec<Entity>}, // Recast from its holder every turn. The targets are those it hit last.
+    Crescent, // How many creatures the holder's last CrescentArc hit. Keep the flame burning!
 }
 
-pub fn match_effect_with_decay(
-    effect: &EffectType
-) -> TriggerType {
-    match effect {
-        EffectType::Discipline => TriggerType::Move,
-        EffectType::Glamour => TriggerType::DealDamage,
-        EffectType::Grace => TriggerType::CastSoul,
-        EffectType::Pride => TriggerType::TakeDamage,
-        _ => TriggerType::EachTurn
+impl EffectType {
+    // Whether this is the same status as another, to stack onto it. Payloads are state that changes or depends
+    // on the caster, not identity, except for the axiom a Link recasts: two different Links are two effects.
+    pub fn is_same_as(&self, other: &EffectType) -> bool {
+        match (self, other) {
+            (EffectType::Link { axiom, .. }, EffectType::Link { axiom: other_axiom, .. }) => axiom == other_axiom,
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
     }
 }
 
-pub fn match_effect_with_gain(
-    effect: &EffectType
-) -> TriggerType {
-    match effect {
-        EffectType::Grace => TriggerType::Move,
-        EffectType::Pride => TriggerType::DealDamage,
-        EffectType::Glamour => TriggerType::CastSoul,
-        EffectType::Discipline => TriggerType::TakeDamage,
-        _ => TriggerType::Never
+// Everything an effect does on its own, so a new status only needs an entry here.
+#[derive(Clone, Debug)]
+pub struct EffectDefinition {
+    pub decay: TriggerType, // Loses a stack whenever this fires, down to the minimum.
+    pub gain: TriggerType,
+    pub minimum: usize,
+    pub maximum: usize,
+    pub stacking: Stacking,
+    pub sprite: usize,
+    pub on_apply: Vec<Function>, // Cast on the holder, by whoever applied it.
+    pub on_tick: Vec<Function>, // Cast on the holder each time it decays.
+    pub on_expire: Vec<Function>, // Cast on the holder once it runs out of stacks.
+    pub overflow: Option<(usize, Vec<Function>)>, // Past this many stacks, these are cast and the effect is gone.
+}
+
+impl Default for EffectDefinition {
+    fn default() -> Self {
+        EffectDefinition { decay: TriggerType::EachTurn, gain: TriggerType::Never, minimum: 0, maximum: usize::MAX, stacking: Stacking::Add, sprite: 1,
+            on_apply: Vec::new(), on_tick: Vec::new(), on_expire: Vec::new(), overflow: None }
     }
 }
 
-pub fn match_effect_with_minimum(
+// What happens when an effect lands on a creature that already has it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stacking {
+    Add,
+    Refresh, // The new stacks replace the old ones.
+    Max,
+    Unique, // The old one stays, and the new one fizzles.
+}
+
+pub const VIRTUE_CAP: usize = 12; // Enough Grace to act on every tick, and no more.
+
+pub fn match_effect_with_definition(
     effect: &EffectType
-) -> usize {
+) -> EffectDefinition {
+    let virtue = EffectDefinition { minimum: 1, maximum: VIRTUE_CAP, ..EffectDefinition::default() };
     match effect {
-        EffectType::Discipline => 1,
-        EffectType::Glamour => 1,
-        EffectType::Grace => 1,
-        EffectType::Pride => 1,
-        _ => 0
+        EffectType::Glamour => EffectDefinition { decay: TriggerType::DealDamage, gain: TriggerType::CastSoul, sprite: 160, ..virtue },
+        EffectType::Discipline => EffectDefinition { decay: TriggerType::Move, gain: TriggerType::TakeDamage, sprite: 161, ..virtue },
+        EffectType::Grace => EffectDefinition { decay: TriggerType::CastSoul, gain: TriggerType::Move, sprite: 164, ..virtue },
+        EffectType::Pride => EffectDefinition { decay: TriggerType::TakeDamage, gain: TriggerType::DealDamage, sprite: 165, ..virtue },
+        // The anchor goes back to the body it came from.
+        EffectType::Possession { link } => EffectDefinition { stacking: Stacking::Unique, on_expire: vec![Function::AimAt { target: *link, of: Box::new(Function::SwapAnchor) }], ..EffectDefinition::default() },
+        EffectType::Polymorph { original } => EffectDefinition { on_expire: vec![Function::PolymorphNow { new_species: original.clone() }], ..EffectDefinition::default() },
+        EffectType::Charm { original } => EffectDefinition { on_expire: vec![Function::SetFaction { faction: original.clone() }], ..EffectDefinition::default() },
+        // Bumping into an open door again keeps it open, but no longer than a fresh opening would.
+        EffectType::OpenDoor => EffectDefinition { stacking: Stacking::Max, on_expire: vec![Function::CloseDoor], ..EffectDefinition::default() },
+        EffectType::Meltdown => EffectDefinition { overflow: Some((9, vec![Function::BlinkOuter])), ..EffectDefinition::default() },
+        EffectType::Link { .. } => EffectDefinition { stacking: Stacking::Refresh, sprite: 139, ..EffectDefinition::default() },
+        EffectType::Crescent => EffectDefinition { decay: TriggerType::Never, stacking: Stacking::Refresh, sprite: 14, ..EffectDefinition::default() },
+        _ => EffectDefinition::default(),
     }
 }
 
@@ -73,7 +106,7 @@ pub fn reduce_down_to(
     }
 }
 
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub enum TriggerType {
     EachTurn,
     DealDamage,
@@ -83,19 +116,7 @@ pub enum TriggerType {
     Never,
 }
 
-pub fn match_effect_with_sprite(
-    effect: &EffectType
-) -> usize {
-    match effect {
-        EffectType::Glamour => 160,
-        EffectType::Discipline => 161,
-        EffectType::Grace => 164,
-        EffectType::Pride => 165,
-        _ => 1,
-    }
-}
-
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum PlantAxiom {
     RandomHighest,
 
@@ -104,7 +125,7 @@ pub enum PlantAxiom {
     TimePasses,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Form {
     Empty,
     Ego,
@@ -115,28 +136,51 @@ pub enum Form {
     SmallBurst,
     BigOuter,
     Artificial { coords: Vec<(Entity, (usize, usize))> },
+    Cone { length: usize, spread: usize }, // Widens by one tile on each side per step along momentum, up to spread.
+    Ring { inner: usize, outer: usize },
+    Chain { bounces: usize, range: usize }, // Jumps to the nearest creature not yet hit, bounces times after the first.
+    Flood { radius: usize }, // Spills through empty tiles, soaking the creatures it runs into.
+    AllOfSpecies { species: Box<Species> },
+    AllOfFaction { faction: Faction },
+    PiercingBeam { max_hits: usize }, // A beam along momentum that goes through creatures, until it has hit this many.
+    CrescentArc, // Sweeps around the caster from its momentum, one tile further for each creature hit on the last swipe.
 }
 
 pub fn match_form_with_name (
     form: Form
 ) -> &'static str {
     match form {
+        Form::Empty => "Nothing",
         Form::Ego => "Self",
         Form::MomentumBeam => "Momentum Beam",
         Form::MomentumTouch => "Momentum Touch",
         Form::MomentumTail => "Momentum Tail",
         Form::MomentumLateral => "Momentum Lateral",
-        _ => "TODO",
+        Form::SmallBurst => "Small Burst",
+        Form::BigOuter => "Outer Circle",
+        Form::Artificial { .. } => "Chosen Tiles",
+        Form::Cone { .. } => "Momentum Cone",
+        Form::Ring { .. } => "Ring",
+        Form::Chain { .. } => "Chain",
+        Form::Flood { .. } => "Flood",
+        Form::AllOfSpecies { .. } => "All of a Species",
+        Form::AllOfFaction { .. } => "All of a Faction",
+        Form::PiercingBeam { .. } => "Piercing Beam",
+        Form::CrescentArc => "Crescent Arc",
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Function {
     Empty,
     Dash { dx: i32, dy: i32 }, // Position is incremented by dx and dy, but stops when it hits an edge or a creature.
     Teleport { x: isize, y: isize }, // 
     FlatMomentumDash { dist: usize },
-    DiscardSoul { soul: Entity, slot: usize },
+    DiscardSoul { soul: Soul, slot: usize }, // A cast soul leaves the hand for the discard.
+    DrawSoul { slot: usize }, // The top soul of a random caste's pile goes into this slot of the hand, if there is room.
+    ShuffleSouls, // The whole discard goes back into the pile.
+    RefillHand, // As many draws as the hand is missing souls, once this turn's casts are all discarded.
+    ExhaustSouls { num: usize }, // Souls taken from the discard and removed from play.
     FlatStealSouls { dam: usize },
     SwapAnchor,
     RedirectSouls { dam: usize, dest: Entity},
@@ -145,7 +189,7 @@ pub enum Function {
     ApplyEffect { effect: Effect },
     MomentumSlamDash {dist: usize},
     MeleeSlam {dist: usize},
-    TriggerEffect {trig: TriggerType},
+    TriggerEffect {trig: TriggerType, amount: usize}, // How much damage was dealt or taken, how far the creature moved...
     PolymorphNow {new_species: Species},
     Charm {dur: usize},
     InjectCaste {num: usize, caste: Soul},
@@ -170,6 +214,71 @@ pub enum Function {
     CyanCharm, //Pride
 
     Duplicate,
+
+    // Functions made of other Functions, so new spells can be written as data.
+    Sequence(Vec<Function>), // Each in order, on the same target.
+    IfTargetHas { effect: EffectType, then: Box<Function>, otherwise: Box<Function> }, // Any stacks of this kind of effect, whatever it carries.
+    Repeat { times: usize, of: Box<Function> },
+    ScaleBy { virtue: EffectType, of: Box<Function> }, // Multiplied by the caster's stacks of this virtue.
+    ScaleByTrigger { of: Box<Function> }, // Multiplied by the amount of the trigger that fired this ReactiveAxiom.
+    AimAt { target: Entity, of: Box<Function> }, // Sent on to another creature, by the same caster.
+
+    Reprogram { caste: usize, program: Box<Species>, from: Entity }, // Installs what a FormCrate or FunctionCrate carries. The crate gets the old piece back.
+
+    // Left behind by effects as they come and go.
+    SetFaction { faction: Faction },
+    CloseDoor, // Shuts an open door, unless someone is standing in it.
+}
+
+// Sent by dispense_functions for every Function it resolves, whether it did anything or not.
+#[allow(dead_code)] // Kept whole in the AxiomLog, for tests and debugging to dig through.
+#[derive(Event, Clone, Debug)]
+pub struct AxiomEvent {
+    pub caster: Entity,
+    pub target: AxiomTarget,
+    pub function: Function,
+    pub outcome: AxiomOutcome,
+    pub error: Option<AxiomError>, // Why it fizzled, if it was cut off by something it expected to find.
+    pub queued: Vec<(AxiomTarget, Function)>, // Follow-up Functions this one pushed onto the axiom queues.
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AxiomTarget {
+    Creature(Entity),
+    Tile((usize, usize)),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AxiomOutcome {
+    Applied,
+    Fizzled, // The target no longer exists, or the Function made no sense where it landed.
+    Blocked, // Something was in the way.
+}
+
+// Creatures can vanish halfway through a chain. Whatever was aimed at them fizzles instead.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AxiomError {
+    UnknownFunction(Function),
+    MissingCreature(Entity),
+    NoSoulsLeft(Entity),
+}
+
+impl fmt::Display for AxiomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AxiomError::UnknownFunction(function) => write!(f, "{function:?} cannot be resolved here"),
+            AxiomError::MissingCreature(entity) => write!(f, "creature {entity:?} does not exist"),
+            AxiomError::NoSoulsLeft(entity) => write!(f, "creature {entity:?} has no Souls left to draw"),
+        }
+    }
+}
+
+pub const AXIOM_LOG_LENGTH: usize = 256;
+
+// The last few AxiomEvents, for whoever did not catch them as they were sent.
+#[derive(Resource, Default)]
+pub struct AxiomLog {
+    pub events: VecDeque<AxiomEvent>,
 }
 
 pub fn match_soul_with_axiom(
@@ -196,6 +305,177 @@ pub fn match_axiom_with_soul(
     }
 }
 
+/*
+
+The axiom scheduler. Functions resolve phase by phase: everything that moves, then everything that bumps
+into something, then damage, then triggered effects, then the end-of-turn expiry of effects. Whenever a
+Function queues up another one, the earliest phase with anything left in it goes next, so a Dash that ends
+in a Collide is settled before anyone's souls get stolen.
+
+Within a phase:
+- Functions queued before resolution begins (this turn's walks and casts) go in order of their caster's
+  species priority, lowest first, then in the order they were queued.
+- Functions derived while resolving go right after the Function that queued them (DepthFirst), or behind
+  everything already waiting in their phase (BreadthFirst), as set by the Resolution resource (TGFP_RESOLUTION). Either way,
+  siblings keep the order they were queued in.
+
+*/
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Phase {
+    Movement,
+    Collision,
+    Damage,
+    Trigger,
+    Expiry,
+}
+
+pub const PHASES: [Phase; 5] = [Phase::Movement, Phase::Collision, Phase::Damage, Phase::Trigger, Phase::Expiry];
+
+pub fn match_function_with_phase(
+    function: &Function
+) -> Phase {
+    match function {
+        Function::Dash { .. } | Function::Teleport { .. } | Function::FlatMomentumDash { .. } | Function::MomentumDash | Function::MomentumReverseDash |
+        Function::MomentumSlamDash { .. } | Function::MeleeSlam { .. } | Function::BlinkOuter | Function::AlterMomentum { .. } |
+        Function::ResetVertical | Function::ResetHorizontal => Phase::Movement,
+        Function::Collide { .. } | Function::BecomeIntangible | Function::BecomeTangible | Function::SummonCreature { .. } | Function::Duplicate => Phase::Collision,
+        Function::FlatStealSouls { .. } | Function::StealSouls | Function::Coil | Function::RedirectSouls { .. } | Function::InjectCaste { .. } |
+        Function::CyanCharm | Function::DiscardSoul { .. } | Function::DrawSoul { .. } | Function::ShuffleSouls | Function::ExhaustSouls { .. } => Phase::Damage,
+        Function::TriggerEffect { trig: TriggerType::EachTurn, .. } | Function::RefillHand => Phase::Expiry,
+        // Unpacked in the earliest phase of what they hold, so nothing gets ahead of their first Function.
+        Function::Sequence(functions) => functions.iter().map(match_function_with_phase).min().unwrap_or(Phase::Trigger),
+        Function::IfTargetHas { then, otherwise, .. } => match_function_with_phase(then).min(match_function_with_phase(otherwise)),
+        Function::Repeat { of, .. } | Function::ScaleBy { of, .. } | Function::ScaleByTrigger { of } | Function::AimAt { of, .. } => match_function_with_phase(of),
+        _ => Phase::Trigger,
+    }
+}
+
+// The same Function, hitting `factor` times as hard. Those with no number to scale are repeated instead.
+pub fn scale_function(
+    function: Function,
+    factor: usize,
+) -> Function {
+    match function {
+        Function::Dash { dx, dy } => Function::Dash { dx: dx * factor as i32, dy: dy * factor as i32 },
+        Function::FlatMomentumDash { dist } => Function::FlatMomentumDash { dist: dist * factor },
+        Function::FlatStealSouls { dam } => Function::FlatStealSouls { dam: dam * factor },
+        Function::RedirectSouls { dam, dest } => Function::RedirectSouls { dam: dam * factor, dest },
+        Function::ApplyEffect { effect } => Function::ApplyEffect { effect: Effect { stacks: effect.stacks * factor, effect_type: effect.effect_type } },
+        Function::MomentumSlamDash { dist } => Function::MomentumSlamDash { dist: dist * factor },
+        Function::MeleeSlam { dist } => Function::MeleeSlam { dist: dist * factor },
+        Function::Charm { dur } => Function::Charm { dur: dur * factor },
+        Function::InjectCaste { num, caste } => Function::InjectCaste { num: num * factor, caste },
+        Function::ExhaustSouls { num } => Function::ExhaustSouls { num: num * factor },
+        Function::AlterMomentum { alter } => Function::AlterMomentum { alter: (alter.0 * factor as i32, alter.1 * factor as i32) },
+        Function::Sequence(functions) => Function::Sequence(functions.into_iter().map(|function| scale_function(function, factor)).collect()),
+        Function::IfTargetHas { effect, then, otherwise } => Function::IfTargetHas { effect, then: Box::new(scale_function(*then, factor)), otherwise: Box::new(scale_function(*otherwise, factor)) },
+        Function::Repeat { times, of } => Function::Repeat { times, of: Box::new(scale_function(*of, factor)) },
+        Function::ScaleBy { virtue, of } => Function::ScaleBy { virtue, of: Box::new(scale_function(*of, factor)) },
+        Function::ScaleByTrigger { of } => Function::ScaleByTrigger { of: Box::new(scale_function(*of, factor)) },
+        Function::AimAt { target, of } => Function::AimAt { target, of: Box::new(scale_function(*of, factor)) },
+        function => Function::Repeat { times: factor, of: Box::new(function) },
+    }
+}
+
+// Settle every ScaleByTrigger in a reaction, now that the amount of its trigger is known.
+pub fn fill_trigger_amount(
+    function: Function,
+    amount: usize,
+) -> Function {
+    let fill = |function: Box<Function>| Box::new(fill_trigger_amount(*function, amount));
+    match function {
+        Function::ScaleByTrigger { of } => scale_function(*fill(of), amount),
+        Function::Sequence(functions) => Function::Sequence(functions.into_iter().map(|function| fill_trigger_amount(function, amount)).collect()),
+        Function::IfTargetHas { effect, then, otherwise } => Function::IfTargetHas { effect, then: fill(then), otherwise: fill(otherwise) },
+        Function::Repeat { times, of } => Function::Repeat { times, of: fill(of) },
+        Function::ScaleBy { virtue, of } => Function::ScaleBy { virtue, of: fill(of) },
+        Function::AimAt { target, of } => Function::AimAt { target, of: fill(of) },
+        function => function,
+    }
+}
+
+// Settable as a resource, and picked up by each queue when resolution begins.
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Resolution {
+    #[default]
+    DepthFirst,
+    BreadthFirst,
+}
+
+impl Resolution {
+    // TGFP_RESOLUTION=breadth resolves breadth-first. Anything else, or nothing, stays depth-first.
+    pub fn from_env() -> Self {
+        match std::env::var("TGFP_RESOLUTION").as_deref() {
+            Ok("breadth") => Resolution::BreadthFirst,
+            _ => Resolution::DepthFirst,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AxiomQueue<T> {
+    phases: [VecDeque<(T, Function, CasterInfo)>; 5],
+    derived: [usize; 5], // Where the next DepthFirst Function goes in each phase.
+    pushed: Vec<(T, Function)>,
+    resolution: Resolution,
+}
+
+impl<T> Default for AxiomQueue<T> {
+    fn default() -> Self {
+        AxiomQueue { phases: Default::default(), derived: [0; 5], pushed: Vec::new(), resolution: Resolution::default() }
+    }
+}
+
+impl<T: Clone> AxiomQueue<T> {
+    pub fn push(
+        &mut self,
+        axiom: (T, Function, CasterInfo),
+    ) {
+        let phase = match_function_with_phase(&axiom.1) as usize;
+        self.pushed.push((axiom.0.clone(), axiom.1.clone()));
+        match self.resolution {
+            Resolution::DepthFirst => {
+                self.phases[phase].insert(self.derived[phase], axiom);
+                self.derived[phase] += 1;
+            }
+            Resolution::BreadthFirst => self.phases[phase].push_back(axiom),
+        }
+    }
+
+    // Put the Functions queued so far in priority order, before resolving the first one.
+    pub fn begin(&mut self, resolution: Resolution) {
+        self.resolution = resolution;
+        for phase in self.phases.iter_mut() {
+            phase.make_contiguous().sort_by_key(|(_, _, info)| match_species_with_priority(&info.species));
+        }
+        self.derived = [0; 5];
+        self.pushed.clear();
+    }
+
+    pub fn pop(&mut self) -> Option<(T, Function, CasterInfo)> {
+        self.derived = [0; 5];
+        self.phases.iter_mut().find_map(|phase| phase.pop_front())
+    }
+
+    pub fn next_phase(&self) -> Option<Phase> {
+        PHASES.iter().zip(&self.phases).find(|(_, queue)| !queue.is_empty()).map(|(phase, _)| *phase)
+    }
+
+    // Everything queued since the last call.
+    pub fn take_pushed(&mut self) -> Vec<(T, Function)> {
+        mem::take(&mut self.pushed)
+    }
+
+    pub fn clear(&mut self) {
+        for phase in self.phases.iter_mut() {
+            phase.clear();
+        }
+        self.derived = [0; 5];
+        self.pushed.clear();
+    }
+}
+
 pub struct ReturnedForm{
     pub entities: Vec<Entity>,
     pub coords: Vec<(usize,usize)>,
@@ -207,18 +487,38 @@ pub struct CasterInfo{
     pub pos: (usize,usize),
     pub species: Species,
     pub momentum: (i32,i32),
-    pub glamour: usize,
-    pub grace: usize,
-    pub discipline: usize,
-    pub pride: usize,
+    pub virtues: Virtues,
+    #[allow(dead_code)] // No Function treats the player differently, yet.
     pub is_player: bool,
     pub effects: Vec<Effect>,
 }
 
+impl CasterInfo {
+    // A snapshot of the creature as it stands, carried along by everything it casts.
+    pub fn new(entity: Entity, pos: &Position, species: &Species, virtues: &Virtues, effects: &[Effect], is_player: bool) -> Self {
+        CasterInfo { entity, pos: (pos.x, pos.y), species: species.clone(), momentum: pos.momentum, virtues: virtues.clone(), is_player, effects: effects.to_vec() }
+    }
+
+    // Stacks of this kind of effect or virtue, whatever the effect carries.
+    pub fn stacks(&self, kind: &EffectType) -> usize {
+        self.virtues.get(kind).unwrap_or_else(|| self.effects.iter().find(|eff| discriminant(&eff.effect_type) == discriminant(kind)).map_or(0, |eff| eff.stacks))
+    }
+}
+
+// What every creature on the map is, for the Forms that pick their targets by Species or Faction.
+pub type Census = HashMap<Entity, (Species, Faction)>;
+
+pub fn take_census<'a>(
+    creatures: impl Iterator<Item = (Entity, &'a Species, &'a Faction)>,
+) -> Census {
+    creatures.map(|(entity, species, faction)| (entity, (species.clone(), faction.clone()))).collect()
+}
+
 pub fn grab_coords_from_form( // vec in vec for better, synchronized animations?
     map: &[Option<Entity>],
     form: Form,
     caster: CasterInfo,
+    census: &Census,
 ) -> ReturnedForm {
     let mut coords = match form {
         Form::Empty => Vec::new(),
@@ -231,6 +531,17 @@ pub fn grab_coords_from_form( // vec in vec for better, synchronized animations?
         Form::SmallBurst => filled_circle(tup_usize_to_i32(caster.pos), 3),
         Form::BigOuter => outer_circle(tup_usize_to_i32(caster.pos), 10),
         Form::Artificial { coords } => coords.into_iter().map(|(_, coords)| coords).collect(),
+        Form::Cone { length, spread } => cone(tup_usize_to_i32(caster.pos), caster.momentum, length as i32, spread as i32),
+        Form::Ring { inner, outer } => ring(tup_usize_to_i32(caster.pos), inner as i32, outer as i32),
+        Form::Chain { bounces, range } => chain(map, caster.pos, caster.entity, bounces, range),
+        Form::Flood { radius } => flood(map, caster.pos, radius),
+        Form::AllOfSpecies { species } => occupied_where(map, |entity| census.get(&entity).is_some_and(|(other, _)| discriminant(other) == discriminant(&*species))),
+        Form::AllOfFaction { faction } => occupied_where(map, |entity| census.get(&entity).is_some_and(|(_, other)| *other == faction)),
+        Form::PiercingBeam { max_hits } => piercing_beam(tup_usize_to_i32(caster.pos), caster.momentum, max_hits, map),
+        Form::CrescentArc => {
+            let last_hits = caster.effects.iter().find(|eff| eff.effect_type == EffectType::Crescent).map_or(0, |eff| eff.stacks);
+            crescent_arc(tup_usize_to_i32(caster.pos), caster.momentum, last_hits + 1)
+        }
     };
     coords.retain(|coordinate| is_in_bounds(coordinate.0 as i32, coordinate.1 as i32));
     let mut entities = Vec::with_capacity(coords.len());
@@ -301,6 +612,146 @@ fn outer_circle(
     coords
 }
 
+fn cone(
+    origin: (i32, i32),
+    momentum: (i32, i32),
+    length: i32,
+    spread: i32,
+) -> Vec<(usize, usize)> {
+    let mut coords = Vec::new();
+    if momentum == (0, 0) { return coords; }
+    let side = (momentum.1, momentum.0);
+    for step in 1..=length {
+        let centre = (origin.0 + momentum.0*step, origin.1 + momentum.1*step);
+        let width = (step-1).min(spread);
+        for offset in -width..=width {
+            coords.push(tup_i32_to_usize((centre.0 + side.0*offset, centre.1 + side.1*offset)));
+        }
+    }
+    coords
+}
+
+fn ring(
+    origin: (i32, i32),
+    inner: i32,
+    outer: i32,
+) -> Vec<(usize, usize)> {
+    let mut coords = Vec::new();
+    for dy in -outer..=outer {
+        for dx in -outer..=outer {
+            let dist = dx*dx + dy*dy;
+            if dist >= inner*inner && dist <= outer*outer {
+                coords.push(tup_i32_to_usize((origin.0 + dx, origin.1 + dy)));
+            }
+        }
+    }
+    coords
+}
+
+fn chain(
+    map: &[Option<Entity>],
+    origin: (usize, usize),
+    caster: Entity,
+    bounces: usize,
+    range: usize,
+) -> Vec<(usize, usize)> {
+    let mut coords: Vec<(usize, usize)> = Vec::new();
+    let mut current = origin;
+    for _ in 0..=bounces {
+        let next = map.iter().enumerate()
+            .filter_map(|(idx, entity)| entity.filter(|entity| *entity != caster).map(|_| idx_xy(idx)))
+            .filter(|tile| !coords.contains(tile))
+            .map(|tile| (tile.0.abs_diff(current.0) + tile.1.abs_diff(current.1), tile))
+            .filter(|(dist, _)| *dist <= range)
+            .min();
+        match next {
+            Some((_, tile)) => {
+                coords.push(tile);
+                current = tile;
+            }
+            None => break,
+        }
+    }
+    coords
+}
+
+fn flood(
+    map: &[Option<Entity>],
+    origin: (usize, usize),
+    radius: usize,
+) -> Vec<(usize, usize)> {
+    let mut coords = Vec::new();
+    let mut seen = vec![origin];
+    let mut frontier = VecDeque::from([(origin, 0)]);
+    while let Some((tile, dist)) = frontier.pop_front() {
+        if dist == radius { continue; }
+        for (nx, ny) in get_neighbours(tile.0, tile.1).into_iter().flatten() {
+            if seen.contains(&(nx, ny)) { continue; }
+            seen.push((nx, ny));
+            coords.push((nx, ny));
+            if map[xy_idx(nx, ny)].is_none() { // Creatures and walls get wet, but the water stops there.
+                frontier.push_back(((nx, ny), dist + 1));
+            }
+        }
+    }
+    coords
+}
+
+// The 8 tiles around a creature, clockwise from straight up.
+const SURROUNDINGS: [(i32, i32); 8] = [(0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0), (-1, 1)];
+
+fn crescent_arc(
+    origin: (i32, i32),
+    momentum: (i32, i32),
+    length: usize,
+) -> Vec<(usize, usize)> {
+    let facing = (momentum.0.signum(), momentum.1.signum());
+    let Some(front) = SURROUNDINGS.iter().position(|dir| *dir == facing) else { return Vec::new() };
+    // Grows from the front tile, one side then the other: 0, +1, -1, +2, -2...
+    (0..length.min(8) as i32).map(|i| {
+        let offset = if i % 2 == 1 { (i+1)/2 } else { -i/2 };
+        let dir = SURROUNDINGS[(front as i32 + offset).rem_euclid(8) as usize];
+        tup_i32_to_usize((origin.0 + dir.0, origin.1 + dir.1))
+    }).collect()
+}
+
+// Remember how many creatures a CrescentArc just hit, for the next swipe.
+pub fn remember_crescent(
+    status: &mut Vec<Effect>,
+    hits: usize,
+) {
+    match status.iter_mut().find(|eff| eff.effect_type == EffectType::Crescent) {
+        Some(eff) => eff.stacks = hits,
+        None => status.push(Effect { stacks: hits, effect_type: EffectType::Crescent }),
+    }
+}
+
+fn occupied_where(
+    map: &[Option<Entity>],
+    predicate: impl Fn(Entity) -> bool,
+) -> Vec<(usize, usize)> {
+    map.iter().enumerate().filter(|(_, entity)| entity.is_some_and(&predicate)).map(|(idx, _)| idx_xy(idx)).collect()
+}
+
+fn piercing_beam(
+    start: (i32, i32),
+    momentum: (i32, i32),
+    max_hits: usize,
+    map: &[Option<Entity>],
+) -> Vec<(usize, usize)> {
+    let mut coords = Vec::new();
+    if momentum == (0, 0) { return coords; }
+    let mut hits = 0;
+    let mut line = bresenham_line(start.0, start.1, start.0 + momentum.0*45, start.1 + momentum.1*45);
+    line.remove(0);
+    for (nx, ny) in line {
+        if !is_in_bounds(nx, ny) || hits >= max_hits { break; }
+        if map[xy_idx(nx as usize, ny as usize)].is_some() { hits += 1; }
+        coords.push(tup_i32_to_usize((nx, ny)));
+    }
+    coords
+}
+
 fn blocked_beam(
     start: (i32,i32),
     end: (i32, i32),
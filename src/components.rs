@@ -1,9 +1,13 @@
+use std::collections::HashMap;
+
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use crate::{axiom::{Effect, Form, Function, PlantAxiom}, input::ActionType, soul::Soul, species::Species};
+use crate::{axiom::{match_effect_with_definition, reduce_down_to, Effect, EffectType, Form, Function, PlantAxiom, TriggerType}, input::ActionType, soul::Soul, species::{calculate_speed, Species, ACTION_COST}};
 
 #[derive(Component)]
 pub struct RealityAnchor {
+    #[allow(dead_code)] // Always 0 until there is more than one player.
     pub player_id: usize,
 }
 
@@ -33,11 +37,6 @@ pub struct Plant {
     pub sequences: Vec<Vec<Soul>>,
 }
 
-#[derive(Component)]
-pub struct BuildQueue {
-    pub build_queue: Vec<(Species, (usize, usize))>
-}
-
 #[derive(Component)]
 pub struct Cursor {
     pub x: usize,
@@ -48,15 +47,28 @@ pub struct Cursor {
 #[derive(Component)]
 pub struct Intangible;
 
+// Creatures brought into existence by an Axiom mid-turn. They fly out of the Reality Anchor when first drawn.
+#[derive(Component)]
+pub struct Summoned;
+
 #[derive(Component)]
 pub struct DoorAnimation;
 
 #[derive(Component)]
 pub struct EffectMarker;
 
+#[derive(Component)]
+pub struct TetherMarker; // One link of the chain drawn between a Link's holder and its targets.
+
+#[derive(Component)]
+pub struct PreviewMarker; // A tile the player's CrescentArc would sweep, shown while choosing an action.
+
 #[derive(Component)]
 pub struct CreatureDescription;
 
+#[derive(Component)]
+pub struct HarmonyGauge; // How close the Serene souls are to critical mass, in the sidebar.
+
 #[derive(Component)]
 pub struct Wounded;
 
@@ -79,13 +91,19 @@ pub struct EffectTracker{
     pub tracking_index: usize,
 }
 
-#[derive(Component, PartialEq, Eq, Hash, Clone, Debug)]
+#[derive(Component)]
+pub struct TurnOrderTracker{
+    pub index: usize,
+}
+
+#[derive(Component, PartialEq, Eq, Hash, Clone, Debug, Default, Serialize, Deserialize)]
 pub enum Faction{
     Saintly,
     Feral,
     Vile,
     Serene,
     Ordered,
+    #[default]
     Unaligned,
 }
 
@@ -100,17 +118,164 @@ pub struct QueuedAction{
     pub action: ActionType,
 }
 
+// Creatures bank energy every tick according to their speed, and spend ACTION_COST of it to act.
+#[derive(Component)]
+pub struct Initiative{
+    pub energy: i32,
+    pub ready: bool, // Whether the creature gets to act this tick.
+}
+
+impl Initiative {
+    // Whether the energy gained next tick will be enough to act.
+    pub fn acts_next(&self, species: &Species, virtues: &Virtues) -> bool {
+        self.energy + calculate_speed(species, virtues) >= ACTION_COST
+    }
+}
+
+/*
+
+The soul deck. Souls are plain values (soul.rs draws them), and every soul a creature owns sits in exactly one zone:
+- pile: the draw pile, one Vec per caste, indexed by match_soul_with_display_index.
+- held: the hand, castable in the order shown on the soul wheel. Never more than hand_size souls.
+- discard: cast and stolen souls, one Vec per caste like the pile.
+- exhaust: souls removed from play by ExhaustSouls. Nothing ever shuffles them back in.
+
+A cast soul goes to the discard (DiscardSoul). Then, depending on the DrawRule, the hand is topped up
+right away in the same slot (OnCast), or all at once at the end of each of the creature's turns (OnTurn, RefillHand).
+Drawing from an empty pile shuffles the discard back into it first (ShuffleSouls), harmonizing one soul
+into a Serene one in passing. Each of those steps is its own Function, so they all show up as AxiomEvents.
+
+*/
+
+pub const DEFAULT_HAND_SIZE: usize = 4;
+pub const MAX_HAND_SIZE: usize = 9; // Each slot is cast with its own number key, and there are only nine of those.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum DrawRule {
+    #[default]
+    OnCast,
+    OnTurn,
+}
+
 #[derive(Component)]
 pub struct SoulBreath{
-    pub pile: Vec<Vec<Entity>>,
-    pub held: Vec<Entity>,
-    pub discard: Vec<Vec<Entity>>,
+    pub pile: Vec<Vec<Soul>>,
+    pub held: Vec<Soul>,
+    pub discard: Vec<Vec<Soul>>,
+    pub exhaust: Vec<Soul>,
+    pub hand_size: usize,
+    pub draw: DrawRule,
     pub soulless: bool,
 }
 
+impl Default for SoulBreath {
+    fn default() -> Self {
+        SoulBreath { pile: vec![Vec::new(); 5], held: Vec::new(), discard: vec![Vec::new(); 5], exhaust: Vec::new(),
+            hand_size: DEFAULT_HAND_SIZE, draw: DrawRule::default(), soulless: false }
+    }
+}
+
+impl SoulBreath {
+    pub fn has_room(&self) -> bool {
+        self.held.len() < self.hand_size
+    }
+
+    // How many souls the hand is missing.
+    pub fn missing(&self) -> usize {
+        self.hand_size.saturating_sub(self.held.len())
+    }
+
+    pub fn pile_is_empty(&self) -> bool {
+        self.pile.iter().all(|caste| caste.is_empty())
+    }
+
+    pub fn discard_is_empty(&self) -> bool {
+        self.discard.iter().all(|caste| caste.is_empty())
+    }
+}
+
+// The four virtues every creature carries, kept apart from its passing effects. How they rise and fall is in match_effect_with_definition.
+#[derive(Component, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Virtues{
+    pub glamour: usize,
+    pub discipline: usize,
+    pub grace: usize,
+    pub pride: usize,
+}
+
+impl Default for Virtues {
+    fn default() -> Self {
+        Virtues { glamour: 1, discipline: 1, grace: 1, pride: 1 }
+    }
+}
+
+impl Virtues {
+    pub const ALL: [EffectType; 4] = [EffectType::Glamour, EffectType::Discipline, EffectType::Grace, EffectType::Pride];
+
+    // None if this kind of effect is not a virtue.
+    pub fn get(&self, virtue: &EffectType) -> Option<usize> {
+        match virtue {
+            EffectType::Glamour => Some(self.glamour),
+            EffectType::Discipline => Some(self.discipline),
+            EffectType::Grace => Some(self.grace),
+            EffectType::Pride => Some(self.pride),
+            _ => None,
+        }
+    }
+
+    fn get_mut(&mut self, virtue: &EffectType) -> Option<&mut usize> {
+        match virtue {
+            EffectType::Glamour => Some(&mut self.glamour),
+            EffectType::Discipline => Some(&mut self.discipline),
+            EffectType::Grace => Some(&mut self.grace),
+            EffectType::Pride => Some(&mut self.pride),
+            _ => None,
+        }
+    }
+
+    // Kept between the virtue's minimum and its cap. Returns false if this is not a virtue.
+    pub fn set(&mut self, virtue: &EffectType, stacks: usize) -> bool {
+        let definition = match_effect_with_definition(virtue);
+        let Some(slot) = self.get_mut(virtue) else { return false };
+        *slot = stacks.clamp(definition.minimum, definition.maximum);
+        true
+    }
+
+    pub fn add(&mut self, virtue: &EffectType, stacks: usize) -> bool {
+        let Some(current) = self.get(virtue) else { return false };
+        self.set(virtue, current + stacks)
+    }
+
+    // Each virtue drains or swells when its own trigger fires.
+    pub fn trigger(&mut self, trig: &TriggerType) {
+        for virtue in Virtues::ALL.iter() {
+            let definition = match_effect_with_definition(virtue);
+            let mut stacks = self.get(virtue).unwrap();
+            if &definition.decay == trig {
+                stacks = reduce_down_to(definition.minimum, stacks, 1);
+            }
+            if &definition.gain == trig {
+                stacks += 1;
+            }
+            self.set(virtue, stacks);
+        }
+    }
+
+    // As effects, for the trackers in the sidebar.
+    pub fn as_effects(&self) -> Vec<Effect> {
+        Virtues::ALL.iter().map(|virtue| Effect { stacks: self.get(virtue).unwrap(), effect_type: virtue.clone() }).collect()
+    }
+}
+
 #[derive(Component)]
 pub struct AxiomEffects{
     pub axioms: Vec<(Form, Function)>,
     pub polarity: Vec<i32>,
     pub status: Vec<Effect>,
+}
+
+// Axioms cast on their own whenever their trigger is dispatched to this creature, like "when taking damage, heal that amount".
+#[derive(Component, Clone, Default)]
+pub struct ReactiveAxioms{
+    pub reactions: HashMap<TriggerType, (Form, Function)>,
 }
\ No newline at end of file
@@ -0,0 +1,644 @@
+use std::fmt;
+
+use serde::de::{self, value::Error as ValueError, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, VariantAccess, Visitor};
+
+use crate::{axiom::{Effect, EffectType, Form, Function}, components::Faction, soul::Soul, species::Species};
+
+/*
+
+A small language to write Axioms by hand, one per line (or separated by ';'):
+
+    beam(momentum) -> steal_souls(pride) @ -2   # Form -> Function @ polarity
+    self -> momentum_dash
+    touch -> flat_steal_souls(3)
+    beam -> summon_creature(chrome_nurse)
+    touch -> if_target_has(open_door, become_tangible, sequence(melee_slam(1), scale_by(pride, flat_steal_souls(1))))
+
+Forms and Functions are the snake_case names of their variants. Those that carry numbers take them in order,
+like cone(length, spread). Species, Factions and Souls are written in snake_case too. The Functions that scale
+with a Virtue may name it, and it is checked. The polarity is optional and defaults to 0. It is how the AI
+judges an Axiom: negative for those meant to hit foes, positive for those meant to hit allies. Everything after
+'#' is a comment.
+
+The composite Functions take other Functions as arguments: sequence(f, g, ...), repeat(times, f),
+scale_by(virtue, f), scale_by_trigger(f) and if_target_has(effect, then, otherwise). The effect is the
+snake_case name of an EffectType that carries nothing, like pride or open_door. link(turns, form, f) tethers
+the target to an Axiom that it casts again every turn until the link runs out.
+
+Species that carry data name each of their fields, like epsilon_tail(order: 1) or airlock(dir: 2). The Form or
+Function held by a crate is written like any other: form_crate(form: beam(momentum)).
+
+*/
+
+#[derive(Debug, PartialEq)]
+pub struct DslError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for DslError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: {}", self.line, self.column, self.message)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Int(i64),
+    LeftParen,
+    RightParen,
+    Comma,
+    Colon,
+    Arrow,
+    At,
+    Separator, // A new line or a ';'.
+    End,
+}
+
+#[derive(Debug, Clone)]
+struct Spanned {
+    token: Token,
+    line: usize,
+    column: usize,
+}
+
+fn error<T>(
+    line: usize,
+    column: usize,
+    message: String,
+) -> Result<T, DslError> {
+    Err(DslError { line, column, message })
+}
+
+fn tokenize(
+    source: &str,
+) -> Result<Vec<Spanned>, DslError> {
+    let mut tokens = Vec::new();
+    for (line_idx, line) in source.lines().enumerate() {
+        let line_no = line_idx + 1;
+        let chars: Vec<char> = line.chars().collect();
+        let mut i = 0;
+        while i < chars.len() {
+            let column = i + 1;
+            let c = chars[i];
+            let token = match c {
+                '#' => break,
+                ' ' | '\t' | '\r' => { i += 1; continue; }
+                '(' => Token::LeftParen,
+                ')' => Token::RightParen,
+                ',' => Token::Comma,
+                ':' => Token::Colon,
+                '@' => Token::At,
+                ';' => Token::Separator,
+                '-' if chars.get(i+1) == Some(&'>') => { i += 1; Token::Arrow },
+                '-' | '0'..='9' => {
+                    let start = i;
+                    i += 1;
+                    while i < chars.len() && chars[i].is_ascii_digit() { i += 1; }
+                    let text: String = chars[start..i].iter().collect();
+                    match text.parse() {
+                        Ok(number) => tokens.push(Spanned { token: Token::Int(number), line: line_no, column }),
+                        Err(_) => return error(line_no, column, format!("`{text}` is not a number")),
+                    }
+                    continue;
+                }
+                c if c.is_ascii_alphabetic() || c == '_' => {
+                    let start = i;
+                    while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') { i += 1; }
+                    tokens.push(Spanned { token: Token::Ident(chars[start..i].iter().collect()), line: line_no, column });
+                    continue;
+                }
+                _ => return error(line_no, column, format!("unexpected character `{c}`")),
+            };
+            tokens.push(Spanned { token, line: line_no, column });
+            i += 1;
+        }
+        tokens.push(Spanned { token: Token::Separator, line: line_no, column: chars.len() + 1 });
+    }
+    let line = source.lines().count().max(1);
+    tokens.push(Spanned { token: Token::End, line, column: source.lines().last().map_or(0, |l| l.chars().count()) + 1 });
+    Ok(tokens)
+}
+
+// A Form or a Function as written: its name and arguments.
+#[derive(Clone)]
+struct Call {
+    name: String,
+    args: Vec<Spanned>,
+    inner: Vec<Option<Call>>, // The arguments that were written as calls themselves, for composite Functions.
+    names: Vec<Option<Spanned>>, // The field each argument was given to, for Species that carry data.
+    line: usize,
+    column: usize,
+}
+
+struct Parser {
+    tokens: Vec<Spanned>,
+    cursor: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &Spanned {
+        &self.tokens[self.cursor]
+    }
+
+    fn next(&mut self) -> Spanned {
+        let token = self.tokens[self.cursor].clone();
+        if token.token != Token::End { self.cursor += 1; }
+        token
+    }
+
+    fn expect(
+        &mut self,
+        expected: Token,
+        what: &str,
+    ) -> Result<Spanned, DslError> {
+        let token = self.next();
+        if token.token == expected { Ok(token) } else { error(token.line, token.column, format!("expected {what}, found {}", describe_token(&token.token))) }
+    }
+
+    fn call(&mut self) -> Result<Call, DslError> {
+        let token = self.next();
+        let Token::Ident(name) = token.token else {
+            return error(token.line, token.column, format!("expected a name, found {}", describe_token(&token.token)));
+        };
+        let mut args = Vec::new();
+        let mut inner = Vec::new();
+        let mut names = Vec::new();
+        if self.peek().token == Token::LeftParen {
+            self.next();
+            if self.peek().token != Token::RightParen {
+                loop {
+                    let mut name = None;
+                    if matches!(self.peek().token, Token::Ident(_)) && self.tokens[self.cursor + 1].token == Token::Colon {
+                        name = Some(self.next());
+                        self.next();
+                    }
+                    names.push(name);
+                    let arg = self.peek().clone();
+                    if !matches!(arg.token, Token::Ident(_) | Token::Int(_)) {
+                        return error(arg.line, arg.column, format!("expected an argument, found {}", describe_token(&arg.token)));
+                    }
+                    if matches!(arg.token, Token::Ident(_)) && self.tokens[self.cursor + 1].token == Token::LeftParen {
+                        inner.push(Some(self.call()?));
+                    } else {
+                        self.next();
+                        inner.push(None);
+                    }
+                    args.push(arg);
+                    if self.peek().token == Token::Comma { self.next(); } else { break; }
+                }
+            }
+            self.expect(Token::RightParen, "`)`")?;
+        }
+        Ok(Call { name, args, inner, names, line: token.line, column: token.column })
+    }
+}
+
+fn describe_token(
+    token: &Token,
+) -> String {
+    match token {
+        Token::Ident(name) => format!("`{name}`"),
+        Token::Int(number) => format!("`{number}`"),
+        Token::LeftParen => "`(`".to_owned(),
+        Token::RightParen => "`)`".to_owned(),
+        Token::Comma => "`,`".to_owned(),
+        Token::Colon => "`:`".to_owned(),
+        Token::Arrow => "`->`".to_owned(),
+        Token::At => "`@`".to_owned(),
+        Token::Separator => "the end of the Axiom".to_owned(),
+        Token::End => "the end of the text".to_owned(),
+    }
+}
+
+// Parse a list of Axioms, with one polarity for each.
+pub fn parse_axioms(
+    source: &str,
+) -> Result<(Vec<(Form, Function)>, Vec<i32>), DslError> {
+    let mut parser = Parser { tokens: tokenize(source)?, cursor: 0 };
+    let mut axioms = Vec::new();
+    let mut polarity = Vec::new();
+    loop {
+        while parser.peek().token == Token::Separator { parser.next(); }
+        if parser.peek().token == Token::End { break; }
+        let form = build_form(parser.call()?)?;
+        parser.expect(Token::Arrow, "`->`")?;
+        let function = build_function(parser.call()?)?;
+        let pol = if parser.peek().token == Token::At {
+            parser.next();
+            let token = parser.next();
+            match token.token {
+                Token::Int(number) => int_in_range(number, &token)?,
+                other => return error(token.line, token.column, format!("expected a polarity, found {}", describe_token(&other))),
+            }
+        } else { 0 };
+        let end = parser.next();
+        if !matches!(end.token, Token::Separator | Token::End) {
+            return error(end.line, end.column, format!("expected the end of the Axiom, found {}", describe_token(&end.token)));
+        }
+        axioms.push((form, function));
+        polarity.push(pol);
+    }
+    Ok((axioms, polarity))
+}
+
+fn build_form(
+    call: Call,
+) -> Result<Form, DslError> {
+    unnamed(&call)?;
+    let sized = match call.name.as_str() {
+        "cone" => Some((Form::Cone { length: number(&call, 0)?, spread: number(&call, 1)? }, 2)),
+        "ring" => Some((Form::Ring { inner: number(&call, 0)?, outer: number(&call, 1)? }, 2)),
+        "chain" => Some((Form::Chain { bounces: number(&call, 0)?, range: number(&call, 1)? }, 2)),
+        "flood" => Some((Form::Flood { radius: number(&call, 0)? }, 1)),
+        "all_of_species" => Some((Form::AllOfSpecies { species: Box::new(variant::<Species>(&call, 0, "Species")?) }, 1)),
+        "all_of_faction" => Some((Form::AllOfFaction { faction: variant::<Faction>(&call, 0, "Faction")? }, 1)),
+        "piercing_beam" => Some((Form::PiercingBeam { max_hits: number(&call, 0)? }, 1)),
+        _ => None,
+    };
+    if let Some((form, arity)) = sized {
+        return match call.args.get(arity) {
+            Some(extra) => error(extra.line, extra.column, format!("`{}` takes {arity} argument{}", call.name, if arity == 1 { "" } else { "s" })),
+            None => Ok(form),
+        };
+    }
+    let (form, shape) = match call.name.as_str() {
+        "empty" => (Form::Empty, None),
+        "self" | "ego" => (Form::Ego, None),
+        "beam" => (Form::MomentumBeam, Some("momentum")),
+        "tail" => (Form::MomentumTail, Some("momentum")),
+        "lateral" => (Form::MomentumLateral, Some("momentum")),
+        "touch" => (Form::MomentumTouch, Some("momentum")),
+        "burst" => (Form::SmallBurst, Some("small")),
+        "outer" => (Form::BigOuter, Some("big")),
+        "crescent" => (Form::CrescentArc, None),
+        _ => return error(call.line, call.column, format!("unknown Form `{}`", call.name)),
+    };
+    match (call.args.as_slice(), shape) {
+        ([], _) => Ok(form),
+        ([arg], Some(shape)) if arg.token == Token::Ident(shape.to_owned()) => Ok(form),
+        ([arg, ..], Some(shape)) => error(arg.line, arg.column, format!("`{}` can only be written `{}({shape})`", call.name, call.name)),
+        ([arg, ..], None) => error(arg.line, arg.column, format!("`{}` takes no arguments", call.name)),
+    }
+}
+
+fn build_function(
+    call: Call,
+) -> Result<Function, DslError> {
+    unnamed(&call)?;
+    let simple = match call.name.as_str() {
+        "empty" => Some(Function::Empty),
+        "swap_anchor" => Some(Function::SwapAnchor),
+        "blink_outer" => Some(Function::BlinkOuter),
+        "become_intangible" => Some(Function::BecomeIntangible),
+        "become_tangible" => Some(Function::BecomeTangible),
+        "mark_patient" => Some(Function::MarkPatient),
+        "segmentize" => Some(Function::Segmentize),
+        "reset_vertical" => Some(Function::ResetVertical),
+        "reset_horizontal" => Some(Function::ResetHorizontal),
+        "duplicate" => Some(Function::Duplicate),
+        "shuffle_souls" => Some(Function::ShuffleSouls),
+        _ => None,
+    };
+    if let Some(function) = simple {
+        return no_arguments(&call).map(|_| function);
+    }
+    let virtuous = match call.name.as_str() {
+        "momentum_dash" => Some((Function::MomentumDash, "grace")),
+        "momentum_reverse_dash" => Some((Function::MomentumReverseDash, "grace")),
+        "possess_creature" => Some((Function::PossessCreature, "glamour")),
+        "steal_souls" => Some((Function::StealSouls, "pride")),
+        "coil" => Some((Function::Coil, "pride")),
+        "imitate_species" => Some((Function::ImitateSpecies, "discipline")),
+        "swap_species" => Some((Function::SwapSpecies, "discipline")),
+        "synchronize" => Some((Function::Synchronize, "grace")),
+        "cyan_charm" => Some((Function::CyanCharm, "pride")),
+        _ => None,
+    };
+    if let Some((function, virtue)) = virtuous {
+        return match call.args.as_slice() {
+            [] => Ok(function),
+            [arg] if arg.token == Token::Ident(virtue.to_owned()) => Ok(function),
+            [arg, ..] => error(arg.line, arg.column, format!("`{}` scales with {virtue}", call.name)),
+        };
+    }
+    let function = match call.name.as_str() {
+        "dash" => Function::Dash { dx: number(&call, 0)?, dy: number(&call, 1)? },
+        "teleport" => Function::Teleport { x: number(&call, 0)?, y: number(&call, 1)? },
+        "alter_momentum" => Function::AlterMomentum { alter: (number(&call, 0)?, number(&call, 1)?) },
+        "flat_momentum_dash" => Function::FlatMomentumDash { dist: number(&call, 0)? },
+        "flat_steal_souls" => Function::FlatStealSouls { dam: number(&call, 0)? },
+        "message_log" => Function::MessageLog { message_id: number(&call, 0)? },
+        "momentum_slam_dash" => Function::MomentumSlamDash { dist: number(&call, 0)? },
+        "melee_slam" => Function::MeleeSlam { dist: number(&call, 0)? },
+        "charm" => Function::Charm { dur: number(&call, 0)? },
+        "exhaust_souls" => Function::ExhaustSouls { num: number(&call, 0)? },
+        "inject_caste" => Function::InjectCaste { num: number(&call, 0)?, caste: variant::<Soul>(&call, 1, "Soul")? },
+        "summon_creature" => Function::SummonCreature { species: variant::<Species>(&call, 0, "Species")? },
+        "polymorph_now" => Function::PolymorphNow { new_species: variant::<Species>(&call, 0, "Species")? },
+        "sequence" => {
+            argument(&call, 0)?;
+            Function::Sequence((0..call.args.len()).map(|index| inner_function(&call, index)).collect::<Result<_, _>>()?)
+        }
+        "if_target_has" => Function::IfTargetHas {
+            effect: variant::<EffectType>(&call, 0, "effect")?,
+            then: Box::new(inner_function(&call, 1)?),
+            otherwise: Box::new(inner_function(&call, 2)?),
+        },
+        "repeat" => Function::Repeat { times: number(&call, 0)?, of: Box::new(inner_function(&call, 1)?) },
+        "scale_by" => Function::ScaleBy { virtue: variant::<EffectType>(&call, 0, "virtue")?, of: Box::new(inner_function(&call, 1)?) },
+        "scale_by_trigger" => Function::ScaleByTrigger { of: Box::new(inner_function(&call, 0)?) },
+        "link" => Function::ApplyEffect { effect: Effect {
+            stacks: number(&call, 0)?,
+            effect_type: EffectType::Link { axiom: Box::new((build_form(inner_call(&call, 1)?)?, inner_function(&call, 2)?)), targets: Vec::new() },
+        }},
+        _ => return error(call.line, call.column, format!("unknown Function `{}`", call.name)),
+    };
+    let arity = match function {
+        Function::Sequence(ref functions) => functions.len(),
+        Function::IfTargetHas { .. } | Function::ApplyEffect { .. } => 3,
+        Function::Dash { .. } | Function::Teleport { .. } | Function::AlterMomentum { .. } | Function::InjectCaste { .. } |
+        Function::Repeat { .. } | Function::ScaleBy { .. } => 2,
+        _ => 1,
+    };
+    if let Some(extra) = call.args.get(arity) {
+        return error(extra.line, extra.column, format!("`{}` takes {arity} argument{}", call.name, if arity == 1 { "" } else { "s" }));
+    }
+    Ok(function)
+}
+
+// Only the fields of a Species are written with names.
+fn unnamed(
+    call: &Call,
+) -> Result<(), DslError> {
+    match call.names.iter().flatten().next() {
+        Some(name) => error(name.line, name.column, format!("the arguments of `{}` have no names", call.name)),
+        None => Ok(()),
+    }
+}
+
+fn no_arguments(
+    call: &Call,
+) -> Result<(), DslError> {
+    match call.args.first() {
+        Some(arg) => error(arg.line, arg.column, format!("`{}` takes no arguments", call.name)),
+        None => Ok(()),
+    }
+}
+
+fn argument(
+    call: &Call,
+    index: usize,
+) -> Result<&Spanned, DslError> {
+    match call.args.get(index) {
+        Some(arg) => Ok(arg),
+        None => error(call.line, call.column, format!("`{}` is missing its argument number {}", call.name, index + 1)),
+    }
+}
+
+// A Form or Function passed to a composite Function. Those without arguments can be written without parentheses.
+fn inner_call(
+    call: &Call,
+    index: usize,
+) -> Result<Call, DslError> {
+    let arg = argument(call, index)?;
+    match (&call.inner[index], &arg.token) {
+        (Some(inner), _) => Ok(inner.clone()),
+        (None, Token::Ident(name)) => Ok(Call { name: name.clone(), args: Vec::new(), inner: Vec::new(), names: Vec::new(), line: arg.line, column: arg.column }),
+        (None, other) => error(arg.line, arg.column, format!("expected a Form or a Function, found {}", describe_token(other))),
+    }
+}
+
+fn inner_function(
+    call: &Call,
+    index: usize,
+) -> Result<Function, DslError> {
+    build_function(inner_call(call, index)?)
+}
+
+fn int_in_range<T: TryFrom<i64>>(
+    number: i64,
+    token: &Spanned,
+) -> Result<T, DslError> {
+    T::try_from(number).or_else(|_| error(token.line, token.column, format!("`{number}` is out of range here")))
+}
+
+fn number<T: TryFrom<i64>>(
+    call: &Call,
+    index: usize,
+) -> Result<T, DslError> {
+    let arg = argument(call, index)?;
+    match arg.token {
+        Token::Int(number) => int_in_range(number, arg),
+        ref other => error(arg.line, arg.column, format!("expected a number, found {}", describe_token(other))),
+    }
+}
+
+fn camel_case(
+    name: &str,
+) -> String {
+    name.split('_').map(|word| {
+        let mut chars = word.chars();
+        chars.next().map_or(String::new(), |first| first.to_ascii_uppercase().to_string() + chars.as_str())
+    }).collect()
+}
+
+// A Species or a Soul caste, written in snake_case: chrome_nurse is Species::ChromeNurse.
+// Those that carry data name their fields: epsilon_tail(order: 1) is Species::EpsilonTail { order: 1 }.
+fn variant<T: DeserializeOwned>(
+    call: &Call,
+    index: usize,
+    kind: &str,
+) -> Result<T, DslError> {
+    let arg = argument(call, index)?;
+    let Token::Ident(name) = &arg.token else {
+        return error(arg.line, arg.column, format!("expected a {kind}, found {}", describe_token(&arg.token)));
+    };
+    let camel = camel_case(name);
+    let Some(fields) = fields_of::<T>(&camel) else {
+        return error(arg.line, arg.column, format!("unknown {kind} `{name}`"));
+    };
+    let example = fields.iter().map(|field| format!("{field}: ...")).collect::<Vec<_>>().join(", ");
+    let mut written = Vec::new();
+    if let Some(data) = &call.inner[index] {
+        for (index, value) in data.args.iter().enumerate() {
+            let Some(field) = &data.names[index] else {
+                return error(value.line, value.column, format!("the fields of `{name}` are written {name}({example})"));
+            };
+            let Token::Ident(field_name) = &field.token else { unreachable!("Only names are followed by `:`.") };
+            if !fields.contains(&field_name.as_str()) {
+                return error(field.line, field.column, format!("`{name}` has no field `{field_name}`"));
+            }
+            let text = match (field_name.as_str(), &value.token) {
+                ("form", _) => ron::to_string(&build_form(inner_call(data, index)?)?).unwrap_or_default(),
+                ("function", _) => ron::to_string(&build_function(inner_call(data, index)?)?).unwrap_or_default(),
+                (_, Token::Int(number)) => number.to_string(),
+                (_, Token::Ident(other)) if data.inner[index].is_none() => camel_case(other),
+                (_, other) => return error(value.line, value.column, format!("expected the {field_name} of `{name}`, found {}", describe_token(other))),
+            };
+            written.push((field_name.as_str(), text));
+        }
+    }
+    let missing: Vec<String> = fields.iter().filter(|field| !written.iter().any(|(name, _)| name == *field)).map(|field| format!("`{field}`")).collect();
+    if !missing.is_empty() {
+        return error(arg.line, arg.column, format!("`{name}` is missing {}, like {name}({example})", missing.join(" and ")));
+    }
+    let text = if written.is_empty() { camel } else {
+        format!("{camel}({})", written.iter().map(|(field, text)| format!("{field}: {text}")).collect::<Vec<_>>().join(", "))
+    };
+    ron::from_str(&text).or_else(|err| error(arg.line, arg.column, format!("`{name}` can't hold that, {}", err.code)))
+}
+
+// The fields of one variant of an enum, or None if there is no such variant. Serde hands them out to
+// whoever deserializes the variant, so this pretends to do just that.
+fn fields_of<T: DeserializeOwned>(
+    variant: &str,
+) -> Option<&'static [&'static str]> {
+    let mut fields = None;
+    let _ = T::deserialize(VariantFields { variant, fields: &mut fields });
+    fields
+}
+
+struct VariantFields<'a> {
+    variant: &'a str,
+    fields: &'a mut Option<&'static [&'static str]>,
+}
+
+impl<'de> de::Deserializer<'de> for VariantFields<'_> {
+    type Error = ValueError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, ValueError> {
+        Err(de::Error::custom("only enums have variants"))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, ValueError> {
+        visitor.visit_enum(self)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option unit
+        unit_struct newtype_struct seq tuple tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl<'de> EnumAccess<'de> for VariantFields<'_> {
+    type Error = ValueError;
+    type Variant = Self;
+
+    fn variant_seed<S: DeserializeSeed<'de>>(self, seed: S) -> Result<(S::Value, Self), ValueError> {
+        let variant = seed.deserialize(self.variant.into_deserializer())?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> VariantAccess<'de> for VariantFields<'_> {
+    type Error = ValueError;
+
+    fn unit_variant(self) -> Result<(), ValueError> {
+        *self.fields = Some(&[]);
+        Ok(())
+    }
+
+    fn newtype_variant_seed<S: DeserializeSeed<'de>>(self, _seed: S) -> Result<S::Value, ValueError> {
+        Err(de::Error::custom("not a struct variant"))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, _visitor: V) -> Result<V::Value, ValueError> {
+        Err(de::Error::custom("not a struct variant"))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, fields: &'static [&'static str], _visitor: V) -> Result<V::Value, ValueError> {
+        *self.fields = Some(fields);
+        Err(de::Error::custom("only the fields were needed"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_forms_functions_and_polarity() {
+        let (axioms, polarity) = parse_axioms("
+beam(momentum) -> steal_souls(pride) @ -2 # Against foes.
+self -> momentum_dash; touch -> flat_steal_souls(3)
+burst(small) -> summon_creature(chrome_nurse) @ 1
+").unwrap();
+        assert_eq!(axioms, vec![
+            (Form::MomentumBeam, Function::StealSouls),
+            (Form::Ego, Function::MomentumDash),
+            (Form::MomentumTouch, Function::FlatStealSouls { dam: 3 }),
+            (Form::SmallBurst, Function::SummonCreature { species: Species::ChromeNurse }),
+        ]);
+        assert_eq!(polarity, vec![-2, 0, 0, 1]);
+    }
+
+    #[test]
+    fn parses_parameterized_forms() {
+        let (axioms, _) = parse_axioms("cone(3, 1) -> coil; all_of_faction(ordered) -> coil; all_of_species(chrome_nurse) -> coil").unwrap();
+        let forms: Vec<Form> = axioms.into_iter().map(|(form, _)| form).collect();
+        assert_eq!(forms, vec![
+            Form::Cone { length: 3, spread: 1 },
+            Form::AllOfFaction { faction: Faction::Ordered },
+            Form::AllOfSpecies { species: Box::new(Species::ChromeNurse) },
+        ]);
+        let err = parse_axioms("flood(2, 3) -> coil").unwrap_err();
+        assert_eq!(err.message, "`flood` takes 1 argument");
+    }
+
+    #[test]
+    fn parses_composite_functions() {
+        let (axioms, _) = parse_axioms("touch -> if_target_has(open_door, repeat(2, blink_outer), sequence(melee_slam(1), scale_by(pride, flat_steal_souls(1))))").unwrap();
+        assert_eq!(axioms[0].1, Function::IfTargetHas {
+            effect: EffectType::OpenDoor,
+            then: Box::new(Function::Repeat { times: 2, of: Box::new(Function::BlinkOuter) }),
+            otherwise: Box::new(Function::Sequence(vec![
+                Function::MeleeSlam { dist: 1 },
+                Function::ScaleBy { virtue: EffectType::Pride, of: Box::new(Function::FlatStealSouls { dam: 1 }) },
+            ])),
+        });
+        let (axioms, _) = parse_axioms("self -> link(3, beam(momentum), steal_souls)").unwrap();
+        assert_eq!(axioms[0].1, Function::ApplyEffect { effect: Effect {
+            stacks: 3,
+            effect_type: EffectType::Link { axiom: Box::new((Form::MomentumBeam, Function::StealSouls)), targets: Vec::new() },
+        }});
+        let err = parse_axioms("touch -> repeat(2, blink_outer, coil)").unwrap_err();
+        assert_eq!(err.message, "`repeat` takes 2 arguments");
+    }
+
+    #[test]
+    fn parses_species_that_carry_data() {
+        let (axioms, _) = parse_axioms("
+self -> summon_creature(epsilon_tail(order: -1))
+all_of_species(airlock(dir: 2)) -> polymorph_now(form_crate(form: beam(momentum)))
+self -> summon_creature(function_crate(function: repeat(2, coil)))
+").unwrap();
+        assert_eq!(axioms, vec![
+            (Form::Ego, Function::SummonCreature { species: Species::EpsilonTail { order: -1 } }),
+            (Form::AllOfSpecies { species: Box::new(Species::Airlock { dir: 2 }) }, Function::PolymorphNow { new_species: Species::FormCrate { form: Form::MomentumBeam } }),
+            (Form::Ego, Function::SummonCreature { species: Species::FunctionCrate { function: Box::new(Function::Repeat { times: 2, of: Box::new(Function::Coil) }) } }),
+        ]);
+        let err = parse_axioms("self -> summon_creature(epsilon_tail)").unwrap_err();
+        assert_eq!(err.to_string(), "line 1, column 25: `epsilon_tail` is missing `order`, like epsilon_tail(order: ...)");
+        let err = parse_axioms("self -> summon_creature(airlock(2))").unwrap_err();
+        assert_eq!((err.column, err.message.as_str()), (33, "the fields of `airlock` are written airlock(dir: ...)"));
+        let err = parse_axioms("self -> summon_creature(airlock(dir: 2, len: 3))").unwrap_err();
+        assert_eq!((err.column, err.message.as_str()), (41, "`airlock` has no field `len`"));
+        let err = parse_axioms("self -> summon_creature(airlock(dir: -1))").unwrap_err();
+        assert!(err.message.starts_with("`airlock` can't hold that"), "{err}");
+        let err = parse_axioms("self -> dash(dx: 1, dy: 0)").unwrap_err();
+        assert_eq!((err.column, err.message.as_str()), (14, "the arguments of `dash` have no names"));
+    }
+
+    #[test]
+    fn points_at_the_mistake() {
+        let err = parse_axioms("beam -> coil\ntouch -> steal_souls(grace)").unwrap_err();
+        assert_eq!((err.line, err.column), (2, 22));
+        let err = parse_axioms("beam -> coil\n  touch dash(1, 0)").unwrap_err();
+        assert_eq!((err.line, err.column, err.message.as_str()), (2, 9, "expected `->`, found `dash`"));
+        let err = parse_axioms("beam -> summon_creature(dragon)").unwrap_err();
+        assert_eq!(err.to_string(), "line 1, column 25: unknown Species `dragon`");
+    }
+}
@@ -0,0 +1,219 @@
+use std::{fmt, mem};
+
+use bevy::prelude::*;
+
+use crate::{axiom::{match_axiom_with_soul, match_form_with_name, AxiomEvent, AxiomOutcome, Function}, components::{AxiomEffects, CreatureDescription, RealityAnchor, SoulBreath, Virtues, Wounded}, input::{describe_creature, move_cursor}, soul::{match_soul_with_display_index, CurrentEntityInUI, Soul}, species::{match_species_with_name, Species}, text::split_text, turn::describe_function, ui::LogNotice, TurnState};
+
+/*
+
+The soul editor. While examining creatures, E swaps the lore panel for a listing of the creature's four Axioms,
+its Virtues and its soul piles. Once the tools are unlocked, by rewriting an Axiom with a crate for the first time,
+1-4 pick a slot and a second pick swaps that slot's Form (or Function, F to switch) with the first one's.
+Only the player's own body and Wounded creatures can be edited, the others fight back.
+
+*/
+
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SoulEditor>();
+        app.add_systems(Update, unlock_soul_editor);
+        app.add_systems(Update, (edit_souls, draw_soul_editor).chain().after(move_cursor).run_if(in_state(TurnState::ExaminingCreatures)));
+        app.add_systems(OnExit(TurnState::ExaminingCreatures), close_soul_editor);
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct SoulEditor {
+    pub unlocked: bool,
+    pub open: bool,
+    pub part: EditPart,
+    pub picked: Option<(Entity, usize)>, // The first slot of a swap, waiting for the second.
+    pub history: Vec<SoulEdit>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum EditPart {
+    #[default]
+    Form,
+    Function,
+}
+
+// Swap one half of an Axiom between two slots, on the same creature or on two of them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SoulEdit {
+    pub part: EditPart,
+    pub from: (Entity, usize),
+    pub to: (Entity, usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EditError {
+    Locked,
+    SameSlot,
+    MissingCreature(Entity),
+    NotCaptured(Entity),
+    NoSuchSlot(Entity, usize),
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EditError::Locked => write!(f, "the editing tools have not been unlocked yet"),
+            EditError::SameSlot => write!(f, "a slot cannot be swapped with itself"),
+            EditError::MissingCreature(entity) => write!(f, "creature {entity:?} has no Axioms"),
+            EditError::NotCaptured(entity) => write!(f, "creature {entity:?} has not been captured"),
+            EditError::NoSuchSlot(entity, slot) => write!(f, "creature {entity:?} has no Axiom in slot {}", slot + 1),
+        }
+    }
+}
+
+// The player's own body, or a creature beaten down to no souls at all.
+pub fn is_captured(
+    world: &World,
+    entity: Entity,
+) -> bool {
+    world.get::<RealityAnchor>(entity).is_some() || world.get::<Wounded>(entity).is_some()
+}
+
+pub fn validate_soul_edit(
+    world: &World,
+    edit: &SoulEdit,
+) -> Result<(), EditError> {
+    if !world.get_resource::<SoulEditor>().is_some_and(|editor| editor.unlocked) {
+        return Err(EditError::Locked);
+    }
+    if edit.from == edit.to {
+        return Err(EditError::SameSlot);
+    }
+    for (entity, slot) in [edit.from, edit.to] {
+        let Some(effects) = world.get::<AxiomEffects>(entity) else { return Err(EditError::MissingCreature(entity)) };
+        if !is_captured(world, entity) {
+            return Err(EditError::NotCaptured(entity));
+        }
+        if slot >= effects.axioms.len() {
+            return Err(EditError::NoSuchSlot(entity, slot));
+        }
+    }
+    Ok(())
+}
+
+// Validate the edit, carry it out if it holds up, and write the outcome in the log either way.
+pub fn apply_soul_edit(
+    world: &mut World,
+    edit: &SoulEdit,
+) -> Result<(), EditError> {
+    let result = validate_soul_edit(world, edit);
+    let notice = match &result {
+        Ok(()) => {
+            let ((from, from_slot), (to, to_slot)) = (edit.from, edit.to);
+            match edit.part {
+                EditPart::Form => {
+                    let given = world.get::<AxiomEffects>(from).unwrap().axioms[from_slot].0.clone();
+                    let taken = mem::replace(&mut world.get_mut::<AxiomEffects>(to).unwrap().axioms[to_slot].0, given);
+                    world.get_mut::<AxiomEffects>(from).unwrap().axioms[from_slot].0 = taken;
+                }
+                EditPart::Function => {
+                    let given = world.get::<AxiomEffects>(from).unwrap().axioms[from_slot].1.clone();
+                    let taken = mem::replace(&mut world.get_mut::<AxiomEffects>(to).unwrap().axioms[to_slot].1, given);
+                    world.get_mut::<AxiomEffects>(from).unwrap().axioms[from_slot].1 = taken;
+                }
+            }
+            if let Some(mut editor) = world.get_resource_mut::<SoulEditor>() {
+                editor.history.push(edit.clone());
+            }
+            let name = |entity: Entity| world.get::<Species>(entity).map_or_else(String::new, match_species_with_name);
+            format!("The {:?} of the [y]{}[w]'s [l]{:?}[w] Axiom and of the [y]{}[w]'s [l]{:?}[w] Axiom have been swapped.",
+                edit.part, name(from), match_axiom_with_soul(from_slot), name(to), match_axiom_with_soul(to_slot))
+        }
+        Err(error) => format!("[r]The edit was refused:[w] {error}."),
+    };
+    world.send_event(LogNotice(notice));
+    result
+}
+
+fn edit_souls(
+    world: &mut World,
+) {
+    let keys = world.resource::<Input<KeyCode>>();
+    let (toggle, switch) = (keys.just_pressed(KeyCode::E), keys.just_pressed(KeyCode::F));
+    let slot = [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4].iter().position(|key| keys.just_pressed(*key));
+    let target = world.resource::<CurrentEntityInUI>().entity;
+    let mut editor = world.resource_mut::<SoulEditor>();
+    if toggle {
+        editor.open = !editor.open;
+        editor.picked = None;
+    }
+    if !editor.open {
+        return;
+    }
+    if switch {
+        editor.part = match editor.part {
+            EditPart::Form => EditPart::Function,
+            EditPart::Function => EditPart::Form,
+        };
+        editor.picked = None;
+    }
+    let Some(slot) = slot else { return };
+    let Some(from) = editor.picked.take() else {
+        editor.picked = Some((target, slot));
+        return;
+    };
+    let edit = SoulEdit { part: editor.part, from, to: (target, slot) };
+    let _ = apply_soul_edit(world, &edit); // Refusals are already in the log.
+}
+
+fn draw_soul_editor(
+    editor: Res<SoulEditor>,
+    inspected: Res<CurrentEntityInUI>,
+    creatures: Query<(&Species, &AxiomEffects, &Virtues, &SoulBreath, Has<RealityAnchor>, Has<Wounded>)>,
+    mut desc: Query<&mut Text, With<CreatureDescription>>,
+    asset_server: Res<AssetServer>,
+) {
+    let Ok(mut text) = desc.get_single_mut() else { return };
+    let Ok((species, effects, virtues, breath, is_player, is_wounded)) = creatures.get(inspected.entity) else { return };
+    if !editor.open {
+        if editor.is_changed() { // Just closed, so the lore goes back up.
+            *text = describe_creature(species, &asset_server);
+        }
+        return;
+    }
+    let mut listing = format!("[y]{}[w]{}\n", match_species_with_name(species), if is_player || is_wounded { " (captured)" } else { "" });
+    listing += &match editor.unlocked {
+        true => format!("Swapping [l]{:?}s[w]. 1-4 to pick a slot, F to switch.\n\n", editor.part),
+        false => "Rewrite an Axiom with a crate to unlock the editing tools.\n\n".to_owned(),
+    };
+    for (slot, ((form, function), polarity)) in effects.axioms.iter().zip(effects.polarity.iter()).enumerate() {
+        let picked = if editor.picked == Some((inspected.entity, slot)) { "[r]>[w] " } else { "" };
+        let function = if function == &Function::Empty { "Nothing".to_owned() } else { describe_function(function) };
+        listing += &format!("{picked}{}. [l]{:?}[w]: {} -> {} ({polarity:+})\n", slot + 1, match_axiom_with_soul(slot), match_form_with_name(form.clone()), function);
+    }
+    listing += &format!("\nGlamour {}, Discipline {}, Grace {}, Pride {}\n", virtues.glamour, virtues.discipline, virtues.grace, virtues.pride);
+    let piles: Vec<String> = [Soul::Feral, Soul::Ordered, Soul::Vile, Soul::Saintly, Soul::Serene].iter()
+        .map(|soul| format!("{soul:?} {}", breath.pile.get(match_soul_with_display_index(soul)).map_or(0, |pile| pile.len())))
+        .collect();
+    listing += &format!("Piles: {}\nHeld: {}/{} (draws {:?}), Exhausted: {}", piles.join(", "), breath.held.len(), breath.hand_size, breath.draw, breath.exhaust.len());
+    let sections = split_text(&listing, &asset_server).into_iter().map(|(snippet, style)| TextSection::new(snippet, style)).collect();
+    text.sections = sections;
+}
+
+fn close_soul_editor(
+    mut editor: ResMut<SoulEditor>,
+) {
+    editor.open = false;
+    editor.picked = None;
+}
+
+// The first crate pushed into an activator teaches the player how souls are put together.
+fn unlock_soul_editor(
+    mut events: EventReader<AxiomEvent>,
+    mut editor: ResMut<SoulEditor>,
+    mut notices: EventWriter<LogNotice>,
+) {
+    let reprogrammed = events.read().any(|event| matches!(event.function, Function::Reprogram { .. }) && event.outcome == AxiomOutcome::Applied);
+    if reprogrammed && !editor.unlocked {
+        editor.unlocked = true;
+        notices.send(LogNotice("[l]The soul editor is unlocked![w] Press E while examining a creature to rewrite its Axioms.".to_owned()));
+    }
+}
@@ -0,0 +1,124 @@
+use bevy::{prelude::*, sprite::Anchor, text::{BreakLineOn, Text2dBounds}};
+
+use crate::{components::{Faction, HarmonyGauge, SoulBreath}, soul::Soul, text::split_text, ui::LogNotice};
+
+/*
+
+The Harmony. Every reshuffle may harmonize a soul into a Serene one, and Serene souls never turn back.
+At the end of every turn, the Serene souls held, piled and discarded by every creature are tallied up. The log warns
+the player as the tally nears CRITICAL_MASS, and once it gets there, every creature joins the Serene and the run is lost
+as soon as that turn has played out.
+Exhausted souls are out of play, and don't count.
+
+*/
+
+pub struct HarmonyPlugin;
+
+impl Plugin for HarmonyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Harmony>();
+        app.add_systems(Startup, draw_harmony_gauge);
+        app.add_systems(Update, update_harmony_gauge.run_if(resource_changed::<Harmony>()));
+    }
+}
+
+pub const CRITICAL_MASS: usize = 40;
+
+// Percentages of CRITICAL_MASS, each announced once on the way up.
+pub const HARMONY_WARNINGS: [(usize, &str); 3] = [
+    (50, "[c]A distant hum.[w] The Harmony is half-way to critical mass."),
+    (75, "[y]The walls are singing.[w] The Harmony is three quarters of the way to critical mass."),
+    (90, "[r]Everything is humming in unison.[w] The Harmony is about to reach critical mass!"),
+];
+
+#[derive(Resource, Default)]
+pub struct Harmony {
+    pub serene: usize,
+    pub warned: usize, // How many of HARMONY_WARNINGS have been announced.
+    pub critical: bool,
+}
+
+impl Harmony {
+    pub fn percent(&self) -> usize {
+        self.serene * 100 / CRITICAL_MASS
+    }
+}
+
+pub fn count_serene_souls(
+    breath: &SoulBreath,
+) -> usize {
+    breath.pile.iter().chain(breath.discard.iter()).flatten().chain(breath.held.iter())
+        .filter(|soul| **soul == Soul::Serene)
+        .count()
+}
+
+pub fn tally_harmony(
+    mut harmony: ResMut<Harmony>,
+    mut creatures: Query<(&SoulBreath, &mut Faction)>,
+    mut notices: EventWriter<LogNotice>,
+) {
+    if harmony.critical {
+        return;
+    }
+    let serene = creatures.iter().map(|(breath, _faction)| count_serene_souls(breath)).sum();
+    if harmony.serene != serene { // Left alone otherwise, so the gauge is only redrawn when needed.
+        harmony.serene = serene;
+    }
+    let reached = HARMONY_WARNINGS.iter().filter(|(percent, _)| harmony.percent() >= *percent).count();
+    if reached > harmony.warned {
+        notices.send(LogNotice(HARMONY_WARNINGS[reached - 1].1.to_owned()));
+    }
+    if reached != harmony.warned { // Warnings come back if the Harmony recedes and rises again.
+        harmony.warned = reached;
+    }
+    if harmony.serene >= CRITICAL_MASS {
+        harmony.critical = true;
+        for (_breath, mut faction) in creatures.iter_mut() {
+            *faction = Faction::Serene;
+        }
+        notices.send(LogNotice("[c]The Harmony has reached critical mass.[w] All souls sing as one, and Terminal's is no exception.".to_owned()));
+    }
+}
+
+fn draw_harmony_gauge(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    let text_style = TextStyle {
+        font: asset_server.load("Play-Regular.ttf"),
+        font_size: 20.,
+        color: Color::WHITE,
+    };
+    commands.spawn((
+        Text2dBundle {
+            text: Text { sections: vec![TextSection {value: String::new(), style: text_style}], alignment: TextAlignment::Center, linebreak_behavior: BreakLineOn::WordBoundary },
+            transform: Transform {
+                translation: Vec3{ x: 1.2, y: -4.8, z: 0.2},
+                scale: Vec3{x: 1./64., y: 1./64., z: 0.}, // Set to the camera scaling mode fixed size
+                ..default()
+            },
+            text_anchor: Anchor::TopCenter,
+            text_2d_bounds: Text2dBounds {size: Vec2 { x: 200., y: 100. }},
+            ..default()
+        },
+        Name::new("Harmony Gauge"),
+        HarmonyGauge,
+    ));
+}
+
+fn update_harmony_gauge(
+    harmony: Res<Harmony>,
+    mut gauge: Query<&mut Text, With<HarmonyGauge>>,
+    asset_server: Res<AssetServer>,
+) {
+    let Ok(mut text) = gauge.get_single_mut() else { return };
+    const NOTCHES: usize = 20;
+    let filled = (harmony.serene * NOTCHES / CRITICAL_MASS).min(NOTCHES);
+    let colour = match harmony.warned {
+        0 => 'c',
+        1 | 2 => 'y',
+        _ => 'r',
+    };
+    let bar = format!("Harmony\n[{colour}]{}[w]{}\n{}/{}", "|".repeat(filled), ".".repeat(NOTCHES - filled), harmony.serene, CRITICAL_MASS);
+    text.sections = split_text(&bar, &asset_server).into_iter().map(|(snippet, style)| TextSection::new(snippet, style)).collect();
+}
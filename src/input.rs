@@ -2,8 +2,9 @@ use std::time::Duration;
 
 use bevy::{prelude::*, text::BreakLineOn};
 use bevy_tweening::{EaseFunction, Tween, lens::TransformPositionLens, Animator};
+use serde::{Deserialize, Serialize};
 
-use crate::{InputDelay, TurnState, components::{RealityAnchor, QueuedAction, Position, Cursor, LogIndex, CreatureDescription}, map::{is_in_bounds, WorldMap, xy_idx}, axiom::tup_i32_to_usize, soul::CurrentEntityInUI, text::{match_species_with_description, LORE, split_text}, species::Species};
+use crate::{InputDelay, TurnState, replay::Playback, components::{RealityAnchor, QueuedAction, Position, Cursor, LogIndex, CreatureDescription, Initiative, SoulBreath, Virtues}, map::{is_in_bounds, WorldMap, xy_idx}, axiom::tup_i32_to_usize, soul::CurrentEntityInUI, text::{LORE, split_text}, species::{match_species_with_description, Species}};
 
 pub struct InputPlugin;
 
@@ -14,10 +15,8 @@ impl Plugin for InputPlugin {
             down: vec![KeyCode::S, KeyCode::Down],
             right: vec![KeyCode::D, KeyCode::Right],
             left: vec![KeyCode::A, KeyCode::Left],
-            one: vec![KeyCode::Key1],
-            two: vec![KeyCode::Key2],
-            three: vec![KeyCode::Key3],
-            four: vec![KeyCode::Key4],
+            casts: [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4, KeyCode::Key5, KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9]
+                .into_iter().map(|key| vec![key]).collect(),
             cursor: vec![KeyCode::Q],
         });
         app.add_systems(Update, await_input.run_if(in_state(TurnState::AwaitingInput)));
@@ -26,7 +25,7 @@ impl Plugin for InputPlugin {
     }
 }
 
-#[derive(PartialEq, Debug, Clone)]
+#[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub enum ActionType{
     Walk { momentum: (i32, i32)},
     SoulCast {slot: usize},
@@ -34,26 +33,40 @@ pub enum ActionType{
 }
 
 #[derive(Resource, Clone)]
-struct InputBindings{
+pub struct InputBindings{
     up: Vec<KeyCode>,
     down: Vec<KeyCode>,
     left: Vec<KeyCode>,
     right: Vec<KeyCode>,
-    one: Vec<KeyCode>,
-    two: Vec<KeyCode>,
-    three: Vec<KeyCode>,
-    four: Vec<KeyCode>,
+    casts: Vec<Vec<KeyCode>>, // One per slot of the hand, see MAX_HAND_SIZE.
     cursor: Vec<KeyCode>,
 }
 
 fn await_input(
     input: Res<Input<KeyCode>>,
     bindings: Res<InputBindings>,
-    mut player: Query<&mut QueuedAction, With<RealityAnchor>>,
+    mut player: Query<(&mut QueuedAction, &Initiative, &Species, &Virtues, &SoulBreath), With<RealityAnchor>>,
     mut next_state: ResMut<NextState<TurnState>>,
+    playback: Option<ResMut<Playback>>,
+    time: Res<Time>,
 ) {
+    let mut hand_size = 0;
+    if let Ok((mut queued, initiative, species, virtues, breath)) = player.get_single_mut() {
+        hand_size = breath.hand_size;
+        if !initiative.acts_next(species, virtues) { // Too slow to act this tick, let the others have theirs.
+            queued.action = ActionType::Nothing;
+            next_state.set(TurnState::CalculatingResponse);
+            return;
+        }
+    }
     let mut reset_queued = true;
-    let action = if input.any_pressed(bindings.up.clone()){
+    let action = if let Some(mut playback) = playback.filter(|playback| playback.is_playing()) {
+        match playback.next_action(time.delta()) { // The keyboard is ignored until the replay is over.
+            Some(action) => action,
+            None => return,
+        }
+    }
+    else if input.any_pressed(bindings.up.clone()){
         ActionType::Walk { momentum: (0,1)}
     }
     else if input.any_pressed(bindings.down.clone()){
@@ -65,17 +78,8 @@ fn await_input(
     else if input.any_pressed(bindings.right.clone()){
         ActionType::Walk { momentum: (1, 0)}
     }
-    else if input.any_just_pressed(bindings.one.clone()){
-        ActionType::SoulCast { slot: 0 }
-    }
-    else if input.any_just_pressed(bindings.two.clone()){
-        ActionType::SoulCast { slot: 1 }
-    }
-    else if input.any_just_pressed(bindings.three.clone()){
-        ActionType::SoulCast { slot: 2 }
-    }
-    else if input.any_just_pressed(bindings.four.clone()){
-        ActionType::SoulCast { slot: 3 }
+    else if let Some(slot) = bindings.casts.iter().take(hand_size).position(|keys| input.any_just_pressed(keys.clone())){
+        ActionType::SoulCast { slot }
     }
     else if input.any_just_pressed(bindings.cursor.clone()){
         next_state.set(TurnState::ExaminingCreatures);
@@ -86,7 +90,7 @@ fn await_input(
         ActionType::Nothing
     };
     if reset_queued {
-        if let Ok(mut queued) = player.get_single_mut() {
+        if let Ok((mut queued, _initiative, _species, _effects, _breath)) = player.get_single_mut() {
             queued.action = action.clone();
             next_state.set(TurnState::CalculatingResponse);
         } else {
@@ -113,7 +117,7 @@ fn reset_cursor(
     }
 }
 
-fn move_cursor(
+pub fn move_cursor(
     mut cursor: Query<(&mut Cursor, &mut Animator<Transform>, &mut Visibility, &Transform)>,
     player: Query<&Position, With<RealityAnchor>>,
     read_species: Query<&Species>,
@@ -142,22 +146,7 @@ fn move_cursor(
         }
         *desc_vis = Visibility::Hidden;
 
-        let mut text_sections = Vec::new();
-        let crea_of_interest = read_species.get(inspected.entity).unwrap();
-        let chosen_text = match LORE.get(match_species_with_description(crea_of_interest)) {
-            Some(lore) => *lore,
-            None => "\"Hi, Onei here. That text index doesn't exist, so here I am instead. Please report this bug.\"",
-        };
-        let split_text = split_text(chosen_text, &asset_server);
-        for (snippet, style) in split_text {
-            text_sections.push(TextSection::new(snippet, style));
-        }
-        let set_text = Text {
-            sections: text_sections,
-            alignment: TextAlignment::Left,
-            linebreak_behavior: BreakLineOn::WordBoundary
-        };
-        *text = set_text;
+        *text = describe_creature(read_species.get(inspected.entity).unwrap(), &asset_server);
         return;
     }
     delay.time.tick(time.delta());
@@ -193,25 +182,31 @@ fn move_cursor(
                 end: Vec3::new((trans.translation.x *2. + action.0).round()/2., (trans.translation.y *2. + action.1).round()/2., 10.)
             },
         );
-        if let Some(crea) = world_map.entities[xy_idx(pointer.x, pointer.y)] { inspected.entity = crea } else {};
+        if let Some(crea) = world_map.entities[xy_idx(pointer.x, pointer.y)] { inspected.entity = crea }
         anim.set_tweenable(tween);
         delay.time.reset();
 
-        let mut text_sections = Vec::new();
-        let crea_of_interest = read_species.get(inspected.entity).unwrap();
-        let chosen_text = match LORE.get(match_species_with_description(crea_of_interest)) {
-            Some(lore) => *lore,
-            None => "\"Hi, Onei here. That text index doesn't exist, so here I am instead. Please report this bug.\"",
-        };
-        let split_text = split_text(chosen_text, &asset_server);
-        for (snippet, style) in split_text {
-            text_sections.push(TextSection::new(snippet, style));
-        }
-        let set_text = Text {
-            sections: text_sections,
-            alignment: TextAlignment::Left,
-            linebreak_behavior: BreakLineOn::WordBoundary
-        };
-        *text = set_text;
-    }
-}
\ No newline at end of file
+        *text = describe_creature(read_species.get(inspected.entity).unwrap(), &asset_server);
+    }
+}
+
+// The LORE entry of a creature, as shown in the examine panel.
+pub fn describe_creature(
+    species: &Species,
+    asset_server: &Res<AssetServer>,
+) -> Text {
+    let mut text_sections = Vec::new();
+    let chosen_text = match LORE.get(match_species_with_description(species)) {
+        Some(lore) => *lore,
+        None => "\"Hi, Onei here. That text index doesn't exist, so here I am instead. Please report this bug.\"",
+    };
+    let split_text = split_text(chosen_text, asset_server);
+    for (snippet, style) in split_text {
+        text_sections.push(TextSection::new(snippet, style));
+    }
+    Text {
+        sections: text_sections,
+        alignment: TextAlignment::Left,
+        linebreak_behavior: BreakLineOn::WordBoundary
+    }
+}
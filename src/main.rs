@@ -1,19 +1,26 @@
+#![allow(clippy::type_complexity, clippy::too_many_arguments)] // Bevy systems take every query and resource they need as an argument.
+
 use std::time::Duration;
 
 use ai::AIPlugin;
-use bevy::{prelude::*, render::camera::ScalingMode, window::WindowMode};
-use bevy_inspector_egui::quick::WorldInspectorPlugin;
+use bevy::{prelude::*, render::camera::ScalingMode};
 use bevy_mouse_tracking_plugin::{mouse_pos::{MousePosPlugin, InitMouseTracking}, MainCamera};
 use bevy_tweening::{TweeningPlugin, Animator, Tween, EaseFunction, lens::TransformPositionLens};
 use components::*;
+use editor::EditorPlugin;
+use harmony::HarmonyPlugin;
 use input::*;
 use map::{MapPlugin, WorldMap, generate_world_vector, xy_idx};
 use soul::{CurrentEntityInUI, Soul, SoulPlugin};
 use species::{CreatureBundle, Species, is_intangible};
 use turn::TurnPlugin;
 use ui::UIPlugin;
-use vaults::{get_build_sequence, Vault, match_vault_with_spawn_loc, build_spire, build_pit};
-use world::{match_plane_with_vaults, Plane};
+use vaults::{get_build_sequence, match_vault_with_spawn_loc};
+use replay::{Playback, ReplayPlugin};
+use rewind::RewindPlugin;
+use rng::GameRng;
+use save::SavePlugin;
+use world::{match_plane_with_vaults, CurrentPlane, Plane};
 
 mod components;
 mod input;
@@ -27,6 +34,17 @@ mod axiom;
 mod text;
 mod world;
 mod ai;
+mod dsl;
+mod editor;
+mod harmony;
+mod replay;
+mod rewind;
+mod rng;
+mod save;
+#[cfg(test)]
+mod simulation;
+#[cfg(test)]
+mod scenario;
 
 fn main() {
     App::new()
@@ -53,6 +71,11 @@ fn main() {
         .add_plugins(TurnPlugin)
         .add_plugins(SoulPlugin)
         .add_plugins(AIPlugin)
+        .add_plugins(SavePlugin)
+        .add_plugins(ReplayPlugin)
+        .add_plugins(RewindPlugin)
+        .add_plugins(EditorPlugin)
+        .add_plugins(HarmonyPlugin)
         //.add_plugins(
         //    WorldInspectorPlugin::default().run_if(input_toggle_active(true, KeyCode::Escape)),
         //)
@@ -71,12 +94,14 @@ fn main() {
             tiny: 16.,
         })
         .insert_resource(ZoomInEffect{timer: Timer::new(Duration::from_millis(500), TimerMode::Once), destination: Plane::Epsilon})
+        .insert_resource(CurrentPlane{plane: Plane::WorldStem})
         .run();
 }
 
 fn setup(
     mut commands: Commands,
     mut zoom: ResMut<ZoomInEffect>,
+    playback: Option<Res<Playback>>,
 ) {
     let mut camera_bundle = Camera2dBundle {
         transform: Transform {
@@ -90,6 +115,10 @@ fn setup(
     commands.spawn(camera_bundle).add(InitMouseTracking).insert(MainCamera);
     commands.insert_resource(InputDelay{time: Timer::new(Duration::from_millis(50), TimerMode::Once)});
     commands.insert_resource(BuildDelay{time: Timer::new(Duration::from_millis(200), TimerMode::Repeating)});
+    commands.insert_resource(match playback {
+        Some(playback) => GameRng::new(playback.seed), // A replay only plays out the same way with its own seed.
+        None => GameRng::from_env(),
+    });
     zoom.timer.pause();
 }
 
@@ -109,8 +138,8 @@ fn world_swap(
     mut zoom: ResMut<ZoomInEffect>,
     despawn: Query<(Entity, &Species), (With<Position>, Without<RealityAnchor>)>,
     mut player: Query<(Entity, &mut Position),With<RealityAnchor>>,
-    texture_atlas_handle: Res<SpriteSheetHandle>,
     mut map: ResMut<WorldMap>,
+    mut current_plane: ResMut<CurrentPlane>,
     mut commands: Commands, 
 
 ) {
@@ -125,22 +154,21 @@ fn world_swap(
             commands.entity(crea).despawn();
         }
         map.entities = generate_world_vector(); // Empty the map.
-        map.targeted_axioms = Vec::new();
-        let mut player_pos = (0.,0.);
+        map.targeted_axioms.clear();
 
+        current_plane.plane = zoom.destination.clone();
         let vault = match_plane_with_vaults(zoom.destination.clone());
         let spawnpoint = match_vault_with_spawn_loc(vault.clone());
         if let Ok((ent, mut pos)) = player.get_single_mut() {
             (pos.x, pos.y) = spawnpoint;
             map.entities[xy_idx(pos.x, pos.y)] = Some(ent);
-            player_pos = ((22. - pos.x as f32)/2., (8. - pos.y as f32)/2.);
         }
 
         let queue = get_build_sequence(vault, (0,0));
         for task in &queue{
             let position = task.1;
-            let new_creature = CreatureBundle::new(&texture_atlas_handle)
-                .with_data(position.0, position.1, player_pos, None, task.0.clone());
+            let new_creature = CreatureBundle::new()
+                .with_data(position.0, position.1, task.0.clone());
             let entity_id = commands.spawn(new_creature).id();
             if is_intangible(&task.0){
                 commands.entity(entity_id).insert(Intangible);
@@ -155,9 +183,8 @@ enum TurnState {
     AwaitingInput,
     ExaminingCreatures,
     CalculatingResponse,
-    ExecutingTurn,
-    DispensingFunctions,
-    UnpackingAnimation
+    UnpackingAnimation,
+    Harmonized, // The Harmony reached critical mass. Nothing moves anymore.
 }
 
 #[derive(Resource)]
@@ -226,8 +253,8 @@ fn spawn_players(
 ) {
     // Player 1
     let position = (10, 4);
-    let player_1 = CreatureBundle::new(&texture_atlas_handle)
-        .with_data(position.0, position.1, (0.,-7.), None, Species::Terminal);
+    let player_1 = CreatureBundle::new()
+        .with_data(position.0, position.1, Species::Terminal);
     let entity = commands.spawn((
         player_1, 
         RealityAnchor { player_id: 0},
@@ -264,16 +291,15 @@ fn spawn_players(
 }
 
 fn summon_walls(
-    texture_atlas_handle: Res<SpriteSheetHandle>,
     mut commands: Commands, 
 ){
-    let queue = get_build_sequence(Vault::EviePlants, (0,0));////build_spire();//
+    let queue = get_build_sequence(match_plane_with_vaults(Plane::WorldStem), (0,0));////build_spire();//
     let mut plant_segments = Vec::new();
     for task in &queue{
         let position = task.1;
         if task.0 == Species::Void {continue;}
-        let new_creature = CreatureBundle::new(&texture_atlas_handle)
-            .with_data(position.0, position.1, (0.,-7.), None, task.0.clone());
+        let new_creature = CreatureBundle::new()
+            .with_data(position.0, position.1, task.0.clone());
         let entity_id = commands.spawn(new_creature).id();
         if task.0 == Species::PlantSegment {
             plant_segments.push(entity_id);
@@ -3,14 +3,14 @@ use std::time::Duration;
 use bevy::prelude::*;
 use pathfinding::directed::astar::astar;
 
-use crate::{components::{Position, Intangible}, axiom::{Function, CasterInfo, tup_usize_to_i32}, world::{Plane, match_plane_with_vaults}, species::{Species, match_species_with_sprite, match_species_with_rotation, is_invisible}, vaults::{extract_square, match_vault_with_spawn_loc}, SpriteSheetHandle, turn::Animation};
+use crate::{components::{Position, Intangible, RealityAnchor, Summoned}, axiom::{AxiomQueue, tup_usize_to_i32}, world::{Plane, match_plane_with_vaults}, species::{Species, match_species_with_sprite, match_species_with_rotation, is_invisible, CreatureSpriteBundle}, vaults::{extract_square, match_vault_with_spawn_loc}, SpriteSheetHandle, turn::Animation};
 
 pub struct MapPlugin;
 
 impl Plugin for MapPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(WorldMap{ entities: vec![None; WORLD_HEIGHT*WORLD_WIDTH], targeted_axioms: Vec::new(), warp_zones: Vec::new(), floor_axioms: Vec::new(), anim_queue: Vec::new(), animation_timer: Timer::new(Duration::from_millis(1),TimerMode::Repeating), anim_reality_anchor: Entity::PLACEHOLDER});
-        app.add_systems(Update, place_down_new_entities);
+        app.init_resource::<WorldMap>();
+        app.add_systems(Update, (place_down_new_entities, dress_new_creatures));
     }
 }
 
@@ -20,14 +20,20 @@ pub const WORLD_HEIGHT: usize = 45;
 #[derive(Resource)]
 pub struct WorldMap {
     pub entities: Vec<Option<Entity>>,
-    pub targeted_axioms: Vec<(Entity,Function, CasterInfo)>,
-    pub floor_axioms: Vec<((usize, usize), Function, CasterInfo)>,
+    pub targeted_axioms: AxiomQueue<Entity>,
+    pub floor_axioms: AxiomQueue<(usize, usize)>,
     pub warp_zones: Vec<((usize, usize), Plane)>,
     pub anim_queue: Vec<(Entity, Animation)>,
     pub animation_timer: Timer,
     pub anim_reality_anchor: Entity,
 }
 
+impl Default for WorldMap {
+    fn default() -> Self {
+        WorldMap{ entities: generate_world_vector(), targeted_axioms: AxiomQueue::default(), warp_zones: Vec::new(), floor_axioms: AxiomQueue::default(), anim_queue: Vec::new(), animation_timer: Timer::new(Duration::from_millis(1),TimerMode::Repeating), anim_reality_anchor: Entity::PLACEHOLDER}
+    }
+}
+
 pub fn generate_world_vector() -> Vec<Option<Entity>>{
     vec![None; WORLD_HEIGHT*WORLD_WIDTH]
 }
@@ -55,11 +61,8 @@ pub fn get_neighbours(x: usize, y: usize,) -> Vec<Option<(usize, usize)>>{
 
 pub fn get_empty_neighbours(map: &[Option<Entity>], x: usize, y: usize) -> Vec<(usize, usize)> {
     let mut output = Vec::with_capacity(4);
-    for pair in get_neighbours(x, y) {
-        match pair {
-            Some((nx,ny)) => if map[xy_idx(nx, ny)].is_none() { output.push((nx, ny)) },
-            None => (),
-        };
+    for (nx, ny) in get_neighbours(x, y).into_iter().flatten() {
+        if map[xy_idx(nx, ny)].is_none() { output.push((nx, ny)) }
     }
     output
 }
@@ -86,14 +89,43 @@ pub fn is_in_bounds(x: i32, y: i32) -> bool {
 pub fn place_down_new_entities(
     query: Query<(Entity, &Species, &Position, Has<Intangible>), Added<Position>>,
     mut world_map: ResMut<WorldMap>,
+) {
+    for (entity_id, species, position, is_intangible) in query.iter(){
+        let warp_zone = ((position.x, position.y), Plane::Epsilon);
+        if species == &Species::Projector && !world_map.warp_zones.contains(&warp_zone){ // Loaded games already know theirs.
+            world_map.warp_zones.push(warp_zone);
+        }
+        if is_intangible {
+            continue;
+        }
+        // The game also places creatures every frame, so a turn may find them already there.
+        if let Some(other) = world_map.entities[xy_idx(position.x, position.y)].filter(|other| *other != entity_id) {
+            warn!("{entity_id:?} is spawning on top of {other:?} at ({}, {}).", position.x, position.y);
+        }
+        world_map.entities[xy_idx(position.x, position.y)] = Some(entity_id);
+    }
+}
+
+pub fn dress_new_creatures(
+    query: Query<(Entity, &Species, &Position, Has<Summoned>), Added<Position>>,
+    anchor: Query<&Position, With<RealityAnchor>>,
+    anchor_trans: Query<&Transform, With<RealityAnchor>>,
     mut commands: Commands,
     texture_atlas_handle: Res<SpriteSheetHandle>,
 ) {
-    for (entity_id, species, position, is_intangible) in query.iter(){
+    let anchor_pos = if let Ok(pos) = anchor.get_single() { (pos.x, pos.y) } else { (0, 0) };
+    for (entity_id, species, position, is_summoned) in query.iter(){
+        let end = Vec3::new(11. + (position.x as f32 - anchor_pos.0 as f32)/2., 4. + (position.y as f32 - anchor_pos.1 as f32)/2., 0.); // Same hardcoded center as unpack_animations
+        let start = match anchor_trans.get_single() {
+            Ok(trans) if is_summoned => trans.translation,
+            _ => end,
+        };
+        commands.entity(entity_id).insert(CreatureSpriteBundle::new(&texture_atlas_handle, species, start, end));
+        if is_summoned {
+            commands.entity(entity_id).remove::<Summoned>();
+        }
         if species == &Species::Projector{
-            let plane = Plane::Epsilon;
-            world_map.warp_zones.push(((position.x, position.y), plane.clone()));
-            let vault = match_plane_with_vaults(plane);
+            let vault = match_plane_with_vaults(Plane::Epsilon);
             let spawn = match_vault_with_spawn_loc(vault.clone());
             let projection = extract_square(vault, spawn.0, spawn.1);
             for (x, i) in projection.iter().enumerate(){
@@ -121,13 +153,6 @@ pub fn place_down_new_entities(
                 } 
             }
         }
-        if is_intangible {
-            continue;
-        }
-        if world_map.entities[xy_idx(position.x, position.y)] != None {
-            dbg!("THERE IS A CREATURE SPAWNING ON TOP OF ANOTHER AT POSITION ({0}, {1})!", position.x, position.y);
-        }
-        world_map.entities[xy_idx(position.x, position.y)] = Some(entity_id);
     }
 }
 
@@ -162,27 +187,6 @@ pub fn manhattan_distance(a: (i32, i32), b: (i32, i32)) -> i32 {
     (a.0 - b.0).abs() + (a.1 - b.1).abs()
  }
  
- pub fn get_best_move(
-    start: (usize, usize),
-    destination: (usize, usize),
-    movements: Vec<(i32, i32)>,
- ) -> Option<(i32, i32)> { 
-    let mut min_distance = manhattan_distance(tup_usize_to_i32(start), tup_usize_to_i32(destination));
-    if movements.is_empty() { return None };
-    let mut best_movement = movements[0];
- 
-    for movement in movements {
-        let new_start = (start.0 as i32 + movement.0, start.1 as i32+ movement.1);
-        let distance = manhattan_distance(new_start, tup_usize_to_i32(destination));
-        if distance < min_distance {
-            min_distance = distance;
-            best_movement = movement;
-        }
-    }
- 
-    Some(best_movement)
- }
-
  pub fn get_all_factions_except_one(
     vec_vecs: &mut Vec<Vec<Entity>>,
     exclude: usize,
@@ -202,12 +206,11 @@ pub fn manhattan_distance(a: (i32, i32), b: (i32, i32)) -> i32 {
 ) -> Option<(Vec<(i32, i32)>, u32)> {
     let goal = tup_usize_to_i32(destination);
     let init = tup_usize_to_i32(start);
-    let result = astar(&init,
-                   |&(x, y)| detect_walls(x, y, vec![start, destination], map)
-                              .into_iter().map(|p| (p, 1)),
-                   |&(x, y)| (goal.0.abs_diff(x) + goal.1.abs_diff(y)) / 3,
-                   |&p| p == goal);
-    result
+    astar(&init,
+        |&(x, y)| detect_walls(x, y, vec![start, destination], map)
+                   .into_iter().map(|p| (p, 1)),
+        |&(x, y)| (goal.0.abs_diff(x) + goal.1.abs_diff(y)) / 3,
+        |&p| p == goal)
 }
 
 fn detect_walls(
@@ -244,7 +247,7 @@ pub fn get_astar_best_move(
 ) -> Option<(i32, i32)> {
     let astar = pathfind_to_location(start, destination, map);
     match astar {
-        None => return None,
+        None => None,
         Some(path) => {
             if path.0.len() <= 1 {
                 return None;
@@ -0,0 +1,143 @@
+use std::{collections::VecDeque, fs, time::Duration};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{components::{Initiative, QueuedAction, RealityAnchor, Virtues}, input::ActionType, rng::GameRng, species::Species};
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        if let Some(playback) = Playback::from_env() {
+            app.insert_resource(playback);
+        }
+        app.init_resource::<ReplayRecorder>();
+        app.add_systems(Update, write_replay.run_if(resource_changed::<ReplayRecorder>()));
+    }
+}
+
+pub const REPLAY_PATH: &str = "tgfp_replay.ron";
+
+// The seed plus every action the player took is all it takes to play a run again, turn for turn.
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct Replay {
+    pub seed: u64,
+    pub actions: Vec<ActionType>,
+}
+
+// Rewritten to disk every turn, so a crash or a hang still leaves behind the replay leading up to it.
+#[derive(Resource, Default)]
+pub struct ReplayRecorder {
+    pub replay: Replay,
+}
+
+// Actions fed to await_input in place of the keyboard, one every tick of the timer.
+#[derive(Resource)]
+pub struct Playback {
+    pub seed: u64,
+    pub actions: VecDeque<ActionType>,
+    pub timer: Timer,
+}
+
+impl Playback {
+    pub fn new(
+        replay: Replay,
+        speed: Duration,
+    ) -> Self {
+        Playback { seed: replay.seed, actions: replay.actions.into(), timer: Timer::new(speed, TimerMode::Repeating) }
+    }
+
+    // Use the replay at TGFP_REPLAY if it is set, played at one turn every TGFP_REPLAY_SPEED milliseconds.
+    pub fn from_env() -> Option<Self> {
+        let path = std::env::var("TGFP_REPLAY").ok()?;
+        let speed = std::env::var("TGFP_REPLAY_SPEED").ok().and_then(|s| s.parse().ok()).unwrap_or(150);
+        match fs::read_to_string(&path).map_err(|err| err.to_string()).and_then(|text| ron::from_str::<Replay>(&text).map_err(|err| err.to_string())) {
+            Ok(replay) => {
+                info!("Playing back {} actions from {path}.", replay.actions.len());
+                Some(Playback::new(replay, Duration::from_millis(speed)))
+            },
+            Err(err) => {
+                warn!("Could not read the replay at {path}: {err}");
+                None
+            }
+        }
+    }
+
+    // The next action to play, if the timer allows it. Once this runs dry, the keyboard takes over.
+    pub fn next_action(
+        &mut self,
+        delta: Duration,
+    ) -> Option<ActionType> {
+        self.timer.tick(delta);
+        if self.timer.just_finished() {
+            self.actions.pop_front()
+        } else {
+            None
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        !self.actions.is_empty()
+    }
+}
+
+pub fn record_action(
+    player: Query<(&QueuedAction, &Initiative, &Species, &Virtues), With<RealityAnchor>>,
+    rng: Res<GameRng>,
+    mut recorder: ResMut<ReplayRecorder>,
+) {
+    let Ok((queued, initiative, species, virtues)) = player.get_single() else { return };
+    if !initiative.acts_next(species, virtues) { return; } // Ticks the player sits out play themselves.
+    recorder.replay.seed = rng.seed;
+    recorder.replay.actions.push(queued.action.clone());
+}
+
+fn write_replay(
+    recorder: Res<ReplayRecorder>,
+) {
+    if recorder.replay.actions.is_empty() { return; } // Don't clobber the last run's replay before this one has started.
+    match ron::to_string(&recorder.replay) {
+        Ok(text) => if let Err(err) = fs::write(REPLAY_PATH, text) {
+            warn!("Could not write {REPLAY_PATH}: {err}");
+        },
+        Err(err) => warn!("Could not serialize the replay: {err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{components::{Position, SoulBreath}, scenario::{Scenario, WANDERERS}, soul::Soul};
+
+    // Where every creature stands and which souls it has, by entity.
+    fn snapshot(
+        scenario: &mut Scenario,
+    ) -> Vec<(Entity, (usize, usize), Vec<Vec<Soul>>, Vec<Soul>, Vec<Vec<Soul>>)> {
+        let mut query = scenario.sim.world.query::<(Entity, &Position, &SoulBreath)>();
+        let mut creatures: Vec<_> = query.iter(&scenario.sim.world)
+            .map(|(entity, pos, breath)| (entity, (pos.x, pos.y), breath.pile.clone(), breath.held.clone(), breath.discard.clone()))
+            .collect();
+        creatures.sort_by_key(|(entity, ..)| *entity);
+        creatures
+    }
+
+    #[test]
+    fn replays_play_out_the_same_from_their_seed() {
+        let actions = [ActionType::Walk { momentum: (1, 0) }, ActionType::SoulCast { slot: 0 }, ActionType::Walk { momentum: (0, 1) },
+            ActionType::SoulCast { slot: 2 }, ActionType::Nothing, ActionType::Walk { momentum: (-1, 0) }, ActionType::SoulCast { slot: 1 }];
+        let mut recorded = Scenario::seeded(WANDERERS, 42);
+        let mut replay = Replay { seed: 42, actions: Vec::new() };
+        for action in actions.iter().cycle().take(30) {
+            recorded.play(std::slice::from_ref(action));
+            replay.actions.push(action.clone());
+        }
+        assert_ne!(snapshot(&mut recorded), snapshot(&mut Scenario::seeded(WANDERERS, 42))); // Something did happen.
+
+        let replay: Replay = ron::from_str(&ron::to_string(&replay).unwrap()).unwrap();
+        let mut replayed = Scenario::seeded(WANDERERS, replay.seed);
+        replayed.sim.play(&replay);
+        assert_eq!(snapshot(&mut replayed), snapshot(&mut recorded));
+        assert_eq!(replayed.sim.turns(), recorded.sim.turns());
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::{components::{Initiative, RealityAnchor, Virtues}, replay::ReplayRecorder, rng::GameRng, save::{load_world, save_world, SaveFile}, species::Species, TurnState};
+
+pub struct RewindPlugin;
+
+impl Plugin for RewindPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RewindStack>();
+        app.add_systems(Update, rewind_on_key.run_if(in_state(TurnState::AwaitingInput)));
+    }
+}
+
+// How many turns back the player can go.
+pub const REWIND_DEPTH: usize = 20;
+
+// The whole world as it was at the start of each of the last few turns, RNG included,
+// so that replaying a rewound turn rolls the same dice.
+#[derive(Resource, Default)]
+pub struct RewindStack {
+    pub snapshots: VecDeque<(SaveFile, GameRng)>,
+}
+
+pub fn snapshot_turn(
+    world: &mut World,
+) {
+    let mut player = world.query_filtered::<(&Initiative, &Species, &Virtues), With<RealityAnchor>>();
+    if player.iter(world).next().is_some_and(|(initiative, species, virtues)| !initiative.acts_next(species, virtues)) {
+        return; // The player sits this tick out, so there is no decision to take back.
+    }
+    let save = save_world(world);
+    let rng = world.resource::<GameRng>().clone();
+    let mut stack = world.get_resource_or_insert_with(RewindStack::default);
+    stack.snapshots.push_back((save, rng));
+    if stack.snapshots.len() > REWIND_DEPTH {
+        stack.snapshots.pop_front();
+    }
+}
+
+// Undo the last turn. Returns false if there is nothing left to undo.
+pub fn rewind_turn(
+    world: &mut World,
+) -> bool {
+    let Some((save, rng)) = world.get_resource_mut::<RewindStack>().and_then(|mut stack| stack.snapshots.pop_back()) else { return false };
+    if let Err(err) = load_world(world, save) {
+        warn!("Could not rewind: {err}");
+        return false;
+    }
+    world.insert_resource(rng);
+    if let Some(mut recorder) = world.get_resource_mut::<ReplayRecorder>() {
+        recorder.replay.actions.pop(); // That turn never happened.
+    }
+    true
+}
+
+fn rewind_on_key(
+    world: &mut World,
+) {
+    if world.resource::<Input<KeyCode>>().just_pressed(KeyCode::R) && !rewind_turn(world) {
+        info!("There are no more turns to rewind.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::RngCore;
+
+    use super::*;
+    use crate::{axiom::{Effect, EffectType}, components::{AxiomEffects, Position, SoulBreath}, input::ActionType, map::WorldMap, scenario::{Scenario, WANDERERS}, soul::Soul};
+
+    // Play until the player is about to act, which is the world the next turn will snapshot.
+    fn snapshot_next_turn(
+        scenario: &mut Scenario,
+    ) -> SaveFile {
+        while !scenario.sim.player_acts_next() {
+            scenario.sim.tick(ActionType::Nothing);
+        }
+        save_world(&mut scenario.sim.world)
+    }
+
+    // Everyone's place and hand, which outlive the entity ids a load hands out anew.
+    fn creatures(
+        save: &SaveFile,
+    ) -> Vec<(Species, (usize, usize), Vec<Soul>)> {
+        let mut creatures: Vec<_> = save.creatures.iter().map(|crea| (crea.species.clone(), crea.position, crea.held.clone())).collect();
+        creatures.sort_by_key(|(_, position, _)| *position);
+        creatures
+    }
+
+    // Everyone's piles and status, and what stands on each tile, as seen by the rules.
+    fn turn_state(
+        scenario: &mut Scenario,
+    ) -> (Vec<((usize, usize), Vec<Vec<Soul>>, Vec<Effect>)>, Vec<(usize, Species)>) {
+        let mut query = scenario.sim.world.query::<(&Position, &SoulBreath, &AxiomEffects)>();
+        let mut creatures: Vec<_> = query.iter(&scenario.sim.world).map(|(pos, breath, effects)| ((pos.x, pos.y), breath.pile.clone(), effects.status.clone())).collect();
+        creatures.sort_by_key(|(position, _, _)| *position);
+        let tiles = scenario.sim.world.resource::<WorldMap>().entities.iter().enumerate().filter_map(|(idx, tile)| tile.map(|entity| (idx, scenario.species(entity)))).collect();
+        (creatures, tiles)
+    }
+
+    #[test]
+    fn only_the_last_turns_are_kept() {
+        let mut scenario = Scenario::seeded(WANDERERS, 7);
+        let mut turns = Vec::new();
+        for _ in 0..REWIND_DEPTH + 5 {
+            turns.push(snapshot_next_turn(&mut scenario).turns);
+            scenario.play(&[ActionType::Walk { momentum: (1, 0) }]);
+        }
+        let stack = scenario.sim.world.resource::<RewindStack>();
+        let kept: Vec<usize> = stack.snapshots.iter().map(|(save, _)| save.turns).collect();
+        assert_eq!(kept, turns[5..]); // The oldest ones were dropped first.
+    }
+
+    #[test]
+    fn rewinding_restores_the_world_and_its_dice() {
+        let mut scenario = Scenario::seeded(WANDERERS, 7);
+        scenario.wait(1);
+        let before = snapshot_next_turn(&mut scenario);
+        let depth = scenario.sim.world.resource::<RewindStack>().snapshots.len();
+        let mut dice = scenario.sim.world.resource::<GameRng>().clone();
+        scenario.play(&[ActionType::SoulCast { slot: 0 }, ActionType::Walk { momentum: (1, 0) }, ActionType::SoulCast { slot: 1 }]);
+        assert_ne!(creatures(&save_world(&mut scenario.sim.world)), creatures(&before));
+        let (mut rolled, mut unrolled) = (scenario.sim.world.resource::<GameRng>().clone(), dice.clone());
+        assert_ne!((rolled.combat.next_u64(), rolled.souls.next_u64()), (unrolled.combat.next_u64(), unrolled.souls.next_u64()));
+
+        let mut input = Input::<KeyCode>::default();
+        input.press(KeyCode::R);
+        scenario.sim.world.insert_resource(input);
+        let mut schedule = Schedule::default();
+        schedule.add_systems(rewind_on_key);
+        for _ in 0..3 {
+            schedule.run(&mut scenario.sim.world);
+        }
+
+        assert_eq!(scenario.sim.world.resource::<RewindStack>().snapshots.len(), depth);
+        let after = save_world(&mut scenario.sim.world);
+        assert_eq!((after.turns, creatures(&after)), (before.turns, creatures(&before)));
+        let rng = scenario.sim.world.resource_mut::<GameRng>().into_inner();
+        assert_eq!(rng.seed, dice.seed);
+        for (restored, expected) in [(&mut rng.map_gen, &mut dice.map_gen), (&mut rng.combat, &mut dice.combat), (&mut rng.souls, &mut dice.souls)] {
+            assert_eq!(restored.next_u64(), expected.next_u64());
+        }
+    }
+
+    #[test]
+    fn rewinding_twice_takes_back_the_last_two_turns() {
+        let mut scenario = Scenario::seeded(WANDERERS, 7);
+        let player = scenario.anchor().unwrap();
+        scenario.sim.world.get_mut::<AxiomEffects>(player).unwrap().status.push(Effect { stacks: 5, effect_type: EffectType::Meltdown });
+        let mut turns = Vec::new();
+        for action in [ActionType::SoulCast { slot: 0 }, ActionType::Walk { momentum: (1, 0) }, ActionType::SoulCast { slot: 1 }, ActionType::Walk { momentum: (1, 0) }] {
+            snapshot_next_turn(&mut scenario);
+            turns.push(turn_state(&mut scenario));
+            scenario.play(&[action]);
+        }
+        assert_ne!(turn_state(&mut scenario), turns[3]);
+        assert_ne!(turns[3], turns[2]);
+
+        assert!(rewind_turn(&mut scenario.sim.world));
+        assert_eq!(turn_state(&mut scenario), turns[3]);
+        assert!(rewind_turn(&mut scenario.sim.world));
+        assert_eq!(turn_state(&mut scenario), turns[2]);
+        assert_eq!(scenario.sim.world.resource::<ReplayRecorder>().replay.actions.len(), 2);
+    }
+}
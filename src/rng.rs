@@ -0,0 +1,35 @@
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+// Every random roll in the game comes from here, so a run can be replayed exactly from its seed.
+// Each purpose gets its own stream: generating a bigger map does not change who wins a fight.
+#[derive(Resource, Clone)]
+pub struct GameRng {
+    pub seed: u64,
+    pub map_gen: StdRng,
+    pub combat: StdRng,
+    pub souls: StdRng,
+}
+
+impl GameRng {
+    pub fn new(
+        seed: u64
+    ) -> Self {
+        Self {
+            seed,
+            map_gen: StdRng::seed_from_u64(seed),
+            combat: StdRng::seed_from_u64(seed.wrapping_add(1)),
+            souls: StdRng::seed_from_u64(seed.wrapping_add(2)),
+        }
+    }
+
+    // Use the TGFP_SEED environment variable if it is set, otherwise roll a fresh seed.
+    pub fn from_env() -> Self {
+        let seed = match std::env::var("TGFP_SEED").ok().and_then(|s| s.parse().ok()) {
+            Some(seed) => seed,
+            None => rand::thread_rng().gen(),
+        };
+        info!("Game seed: {seed}");
+        GameRng::new(seed)
+    }
+}
@@ -0,0 +1,358 @@
+use std::{collections::HashMap, fs};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{axiom::{Effect, EffectType, Form, Function, TriggerType}, components::{AxiomEffects, DrawRule, Faction, Initiative, Intangible, Plant, Position, ReactiveAxioms, RealityAnchor, Segmentified, SoulBreath, SoulSlot, Thought, Virtues, Wounded, MAX_HAND_SIZE}, map::{xy_idx, WorldMap, WORLD_HEIGHT, WORLD_WIDTH}, soul::{CurrentEntityInUI, Soul}, species::{is_intangible, CreatureBundle, Species}, turn::TurnCount, world::{CurrentPlane, Plane}, TurnState};
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (save_game, load_game).run_if(in_state(TurnState::AwaitingInput)));
+    }
+}
+
+pub const SAVE_VERSION: u32 = 1;
+pub const SAVE_PATH: &str = "tgfp_save.ron";
+
+/*
+
+Entities are written with their ids from the moment of saving. Loading spawns everything anew
+and translates every stored id (the map grid, the Reality Anchor, effect links, plant stems)
+to the freshly spawned entities, down to those held inside Functions.
+
+*/
+
+#[derive(Serialize, Deserialize)]
+pub struct SaveFile {
+    pub version: u32,
+    pub turns: usize,
+    pub plane: Plane,
+    pub anchor: Option<Entity>,
+    pub entities: Vec<Option<Entity>>,
+    pub warp_zones: Vec<((usize, usize), Plane)>,
+    pub creatures: Vec<SavedCreature>,
+    pub plants: Vec<SavedPlant>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+pub struct SavedCreature {
+    pub id: Entity,
+    pub species: Species,
+    pub position: (usize, usize),
+    pub old_position: (usize, usize),
+    pub momentum: (i32, i32),
+    pub faction: Faction,
+    pub energy: i32,
+    pub pile: Vec<Vec<Soul>>,
+    pub held: Vec<Soul>,
+    pub discard: Vec<Vec<Soul>>,
+    pub exhaust: Vec<Soul>,
+    pub hand_size: usize,
+    pub draw: DrawRule,
+    pub soulless: bool,
+    pub axioms: Vec<(Form, Function)>,
+    pub polarity: Vec<i32>,
+    pub status: Vec<Effect>,
+    pub reactions: HashMap<TriggerType, (Form, Function)>,
+    pub virtues: Virtues,
+    pub stored_path: Option<(Vec<(i32, i32)>, u32)>,
+    pub wounded: bool,
+    pub segmentified: bool,
+    pub soul_slot: Option<Option<Soul>>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedPlant {
+    pub stem: Vec<Entity>,
+    pub program: Vec<Vec<crate::axiom::PlantAxiom>>,
+    pub sequences: Vec<Vec<Soul>>,
+}
+
+pub fn save_world(
+    world: &mut World,
+) -> SaveFile {
+    let mut creatures = Vec::new();
+    let mut query = world.query::<(Entity, &Species, &Position, &Faction, &Initiative, &SoulBreath, &AxiomEffects, &ReactiveAxioms, &Virtues, &Thought, Has<Wounded>, Has<Segmentified>, Option<&SoulSlot>)>();
+    for (entity, species, pos, faction, initiative, breath, effects, reactive, virtues, thought, wounded, segmentified, soul_slot) in query.iter(world) {
+        creatures.push(SavedCreature {
+            id: entity,
+            species: species.clone(),
+            position: (pos.x, pos.y),
+            old_position: (pos.ox, pos.oy),
+            momentum: pos.momentum,
+            faction: faction.clone(),
+            energy: initiative.energy,
+            pile: breath.pile.clone(),
+            held: breath.held.clone(),
+            discard: breath.discard.clone(),
+            exhaust: breath.exhaust.clone(),
+            hand_size: breath.hand_size,
+            draw: breath.draw,
+            soulless: breath.soulless,
+            axioms: effects.axioms.clone(),
+            polarity: effects.polarity.clone(),
+            status: effects.status.clone(),
+            reactions: reactive.reactions.clone(),
+            virtues: virtues.clone(),
+            stored_path: thought.stored_path.clone(),
+            wounded,
+            segmentified,
+            soul_slot: soul_slot.map(|slot| slot.slot),
+        });
+    }
+    let mut plants = Vec::new();
+    let mut query = world.query::<&Plant>();
+    for plant in query.iter(world) {
+        plants.push(SavedPlant { stem: plant.stem.clone(), program: plant.program.clone(), sequences: plant.sequences.clone() });
+    }
+    let mut anchor = world.query_filtered::<Entity, With<RealityAnchor>>();
+    let anchor = anchor.iter(world).next();
+    let map = world.resource::<WorldMap>();
+    SaveFile {
+        version: SAVE_VERSION,
+        turns: world.resource::<TurnCount>().turns,
+        plane: world.resource::<CurrentPlane>().plane.clone(),
+        anchor,
+        entities: map.entities.clone(),
+        warp_zones: map.warp_zones.clone(),
+        creatures,
+        plants,
+    }
+}
+
+// Wipe every creature, soul and plant, then rebuild them from the save.
+pub fn load_world(
+    world: &mut World,
+    save: SaveFile,
+) -> Result<(), String> {
+    if save.version != SAVE_VERSION {
+        return Err(format!("Save version {} does not match the game's version {}.", save.version, SAVE_VERSION));
+    }
+    if save.entities.len() != WORLD_WIDTH*WORLD_HEIGHT {
+        return Err(format!("The saved map has {} tiles instead of {}.", save.entities.len(), WORLD_WIDTH*WORLD_HEIGHT));
+    }
+    let mut doomed = world.query_filtered::<Entity, Or<(With<Position>, With<Plant>)>>();
+    let doomed: Vec<Entity> = doomed.iter(world).collect();
+    for entity in doomed {
+        if let Some(entity) = world.get_entity_mut(entity) {
+            entity.despawn_recursive();
+        }
+    }
+
+    let mut remap = HashMap::new();
+    for saved in &save.creatures {
+        let entity = world.spawn(CreatureBundle::new().with_data(saved.position.0, saved.position.1, saved.species.clone())).id();
+        remap.insert(saved.id, entity);
+    }
+    for saved in save.creatures {
+        let entity = remap[&saved.id];
+        let breath = SoulBreath {
+            pile: saved.pile,
+            held: saved.held,
+            discard: saved.discard,
+            exhaust: saved.exhaust,
+            hand_size: saved.hand_size.min(MAX_HAND_SIZE),
+            draw: saved.draw,
+            soulless: saved.soulless,
+        };
+        let status = saved.status.into_iter().map(|effect| remap_effect(effect, &remap)).collect();
+        let axioms = saved.axioms.into_iter().map(|(form, function)| (form, remap_function(function, &remap))).collect();
+        let reactions = saved.reactions.into_iter().map(|(trig, (form, function))| (trig, (form, remap_function(function, &remap)))).collect();
+        let tangible = save.entities.get(xy_idx(saved.position.0, saved.position.1)) == Some(&Some(saved.id));
+        let mut crea = world.entity_mut(entity);
+        crea.insert((
+            Position { x: saved.position.0, y: saved.position.1, ox: saved.old_position.0, oy: saved.old_position.1, momentum: saved.momentum },
+            saved.faction,
+            Initiative { energy: saved.energy, ready: false },
+            breath,
+            AxiomEffects { axioms, polarity: saved.polarity, status },
+            ReactiveAxioms { reactions },
+            saved.virtues,
+            Thought { stored_path: saved.stored_path },
+            remap_species(saved.species.clone(), &remap), // Spawned before every id was known.
+        ));
+        if is_intangible(&saved.species) || !tangible { // Open doors are off the grid too.
+            crea.insert(Intangible);
+        }
+        if saved.wounded {
+            crea.insert(Wounded);
+        }
+        if saved.segmentified {
+            crea.insert(Segmentified);
+        }
+        if let Some(slot) = saved.soul_slot {
+            crea.insert(SoulSlot { slot });
+        }
+    }
+    for plant in save.plants {
+        let stem = plant.stem.iter().filter_map(|seg| remap.get(seg).copied()).collect();
+        world.spawn(Plant { stem, program: plant.program, sequences: plant.sequences });
+    }
+    if let Some(anchor) = save.anchor.and_then(|anchor| remap.get(&anchor).copied()) {
+        world.entity_mut(anchor).insert(RealityAnchor { player_id: 0 });
+        world.resource_mut::<CurrentEntityInUI>().entity = anchor;
+        world.resource_mut::<WorldMap>().anim_reality_anchor = anchor;
+    }
+
+    let mut map = world.resource_mut::<WorldMap>();
+    map.entities = save.entities.iter().map(|tile| tile.and_then(|id| remap.get(&id).copied())).collect(); // Right away, so a rewound world can be looked at before the next turn.
+    map.warp_zones = save.warp_zones;
+    map.targeted_axioms.clear();
+    map.floor_axioms.clear();
+    map.anim_queue.clear();
+    world.resource_mut::<TurnCount>().turns = save.turns;
+    world.resource_mut::<CurrentPlane>().plane = save.plane;
+    Ok(())
+}
+
+fn relink(
+    link: Entity,
+    remap: &HashMap<Entity, Entity>,
+) -> Entity {
+    remap.get(&link).copied().unwrap_or(Entity::PLACEHOLDER)
+}
+
+fn remap_effect(
+    effect: Effect,
+    remap: &HashMap<Entity, Entity>,
+) -> Effect {
+    Effect { stacks: effect.stacks, effect_type: remap_effect_type(effect.effect_type, remap) }
+}
+
+fn remap_effect_type(
+    effect_type: EffectType,
+    remap: &HashMap<Entity, Entity>,
+) -> EffectType {
+    match effect_type {
+        EffectType::Possession { link } => EffectType::Possession { link: relink(link, remap) },
+        EffectType::Sync { link } => EffectType::Sync { link: relink(link, remap) },
+        EffectType::AssignedPatient { link } => EffectType::AssignedPatient { link: relink(link, remap) },
+        EffectType::Link { axiom, targets } => {
+            let (form, function) = *axiom;
+            EffectType::Link { axiom: Box::new((form, remap_function(function, remap))), targets: targets.into_iter().map(|target| relink(target, remap)).collect() }
+        }
+        other => other,
+    }
+}
+
+// Functions can point at creatures too, and so can the effects and crates nested in them.
+fn remap_function(
+    function: Function,
+    remap: &HashMap<Entity, Entity>,
+) -> Function {
+    let inner = |function: Box<Function>| Box::new(remap_function(*function, remap));
+    match function {
+        Function::RedirectSouls { dam, dest } => Function::RedirectSouls { dam, dest: relink(dest, remap) },
+        Function::Collide { with } => Function::Collide { with: relink(with, remap) },
+        Function::AimAt { target, of } => Function::AimAt { target: relink(target, remap), of: inner(of) },
+        Function::Reprogram { caste, program, from } => Function::Reprogram { caste, program: Box::new(remap_species(*program, remap)), from: relink(from, remap) },
+        Function::ApplyEffect { effect } => Function::ApplyEffect { effect: remap_effect(effect, remap) },
+        Function::PolymorphNow { new_species } => Function::PolymorphNow { new_species: remap_species(new_species, remap) },
+        Function::SummonCreature { species } => Function::SummonCreature { species: remap_species(species, remap) },
+        Function::Sequence(functions) => Function::Sequence(functions.into_iter().map(|function| remap_function(function, remap)).collect()),
+        Function::IfTargetHas { effect, then, otherwise } => Function::IfTargetHas { effect: remap_effect_type(effect, remap), then: inner(then), otherwise: inner(otherwise) },
+        Function::Repeat { times, of } => Function::Repeat { times, of: inner(of) },
+        Function::ScaleBy { virtue, of } => Function::ScaleBy { virtue, of: inner(of) },
+        Function::ScaleByTrigger { of } => Function::ScaleByTrigger { of: inner(of) },
+        other => other,
+    }
+}
+
+fn remap_species(
+    species: Species,
+    remap: &HashMap<Entity, Entity>,
+) -> Species {
+    match species {
+        Species::FunctionCrate { function } => Species::FunctionCrate { function: Box::new(remap_function(*function, remap)) },
+        other => other,
+    }
+}
+
+fn save_game(
+    world: &mut World,
+) {
+    if !world.resource::<Input<KeyCode>>().just_pressed(KeyCode::F5) {
+        return;
+    }
+    let save = save_world(world);
+    match ron::ser::to_string_pretty(&save, ron::ser::PrettyConfig::default()) {
+        Ok(text) => match fs::write(SAVE_PATH, text) {
+            Ok(()) => info!("Game saved to {SAVE_PATH}."),
+            Err(err) => warn!("Could not write {SAVE_PATH}: {err}"),
+        },
+        Err(err) => warn!("Could not serialize the game: {err}"),
+    }
+}
+
+fn load_game(
+    world: &mut World,
+) {
+    if !world.resource::<Input<KeyCode>>().just_pressed(KeyCode::F9) {
+        return;
+    }
+    let save = match fs::read_to_string(SAVE_PATH).map_err(|err| err.to_string()).and_then(|text| ron::from_str::<SaveFile>(&text).map_err(|err| err.to_string())) {
+        Ok(save) => save,
+        Err(err) => {
+            warn!("Could not read {SAVE_PATH}: {err}");
+            return;
+        }
+    };
+    match load_world(world, save) {
+        Ok(()) => info!("Game loaded from {SAVE_PATH}."),
+        Err(err) => warn!("Could not load {SAVE_PATH}: {err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::Scenario;
+
+    #[test]
+    fn creatures_come_back_the_same_after_a_save_and_load() {
+        let mut scenario = Scenario::new("
+##########
+#X..G....#
+##########
+");
+        scenario.wait(1);
+        let (crate_entity, player) = (scenario.find(&Species::GlamourCrate), scenario.anchor().unwrap());
+        let reaction = (Form::Ego, Function::Dash { dx: 1, dy: 0 });
+        scenario.sim.world.entity_mut(player).insert((
+            ReactiveAxioms { reactions: HashMap::from([(TriggerType::TakeDamage, reaction)]) },
+            Thought { stored_path: Some((vec![(1, 0), (2, 0)], 3)) },
+        ));
+        let aimed = Function::AimAt { target: crate_entity, of: Box::new(Function::RedirectSouls { dam: 1, dest: player }) };
+        let link = EffectType::Link { axiom: Box::new((Form::Ego, aimed)), targets: vec![crate_entity] };
+        let mut effects = scenario.sim.world.get_mut::<AxiomEffects>(player).unwrap();
+        effects.status.extend([Effect { stacks: 5, effect_type: EffectType::Sync { link: crate_entity } }, Effect { stacks: 3, effect_type: link }]);
+        effects.axioms[0].1 = Function::Collide { with: crate_entity };
+
+        let before = save_world(&mut scenario.sim.world);
+        let text = ron::ser::to_string(&before).unwrap();
+        load_world(&mut scenario.sim.world, ron::from_str(&text).unwrap()).unwrap();
+        let after = save_world(&mut scenario.sim.world);
+
+        // Loading hands out new ids, so creatures are told apart by where they stand.
+        assert_eq!(after.creatures.len(), before.creatures.len());
+        let remap: HashMap<Entity, Entity> = before.creatures.iter()
+            .map(|old| (old.id, after.creatures.iter().find(|new| new.position == old.position).unwrap().id))
+            .collect();
+        assert_eq!(after.anchor, before.anchor.map(|anchor| remap[&anchor]));
+        for old in before.creatures {
+            let id = remap[&old.id];
+            let status = old.status.iter().cloned().map(|effect| remap_effect(effect, &remap)).collect();
+            let axioms = old.axioms.iter().cloned().map(|(form, function)| (form, remap_function(function, &remap))).collect();
+            let expected = SavedCreature { id, status, axioms, ..old };
+            assert_eq!(after.creatures.iter().find(|new| new.id == id), Some(&expected));
+        }
+        // The ids inside the Link's Function point at the new creatures too.
+        let (new_crate, new_player) = (remap[&crate_entity], remap[&player]);
+        let status = &scenario.sim.world.get::<AxiomEffects>(new_player).unwrap().status;
+        let Some(EffectType::Link { axiom, targets }) = status.iter().map(|eff| &eff.effect_type).find(|eff| matches!(eff, EffectType::Link { .. })) else { panic!("The Link was lost!") };
+        assert_eq!(targets, &vec![new_crate]);
+        assert_eq!(axiom.1, Function::AimAt { target: new_crate, of: Box::new(Function::RedirectSouls { dam: 1, dest: new_player }) });
+    }
+}
@@ -0,0 +1,725 @@
+use std::mem::discriminant;
+
+use bevy::prelude::*;
+
+use crate::{axiom::{grab_coords_from_form, take_census, CasterInfo, EffectType, Form, Function}, components::{AxiomEffects, DrawRule, Faction, Position, SoulBreath, Virtues}, input::ActionType, map::{xy_idx, WorldMap}, simulation::Simulation, species::Species, vaults::{get_species_from_char, parse_vault_with_legend}};
+
+/*
+
+Regression tests written as tiny vaults. The vault string uses the same legend as VAULTS, plus 'X' for the
+Terminal holding the Reality Anchor. Like in VAULTS, x grows to the right and y grows upwards from the bottom
+line of the string, which sits at y = 0.
+
+A Scenario plays the player's actions one turn at a time in a Simulation, and the test then checks the state
+of the world with the helpers below.
+
+*/
+
+// The VAULTS legend, plus 'X' for Terminal, which no real vault places by hand.
+fn scenario_legend(
+    char: char,
+) -> Species {
+    match char {
+        'X' => Species::Terminal,
+        _ => get_species_from_char(char),
+    }
+}
+
+// Two LunaMoths and a ChromeNurse roaming about, for tests where the dice have to matter.
+pub const WANDERERS: &str = "
+############
+#..m....A..#
+#X....m....#
+############
+";
+
+pub struct Scenario {
+    pub sim: Simulation,
+}
+
+impl Scenario {
+    pub fn new(
+        vault: &str,
+    ) -> Self {
+        Scenario::seeded(vault, 0)
+    }
+
+    pub fn seeded(
+        vault: &str,
+        seed: u64,
+    ) -> Self {
+        let mut sim = Simulation::new(seed);
+        let mut anchored = false;
+        for (species, (x, y)) in parse_vault_with_legend(vault, (0, 0), scenario_legend) {
+            if species == Species::Terminal && !anchored {
+                sim.spawn_player(species, x, y);
+                anchored = true;
+            } else {
+                sim.spawn_creature(species, x, y);
+            }
+        }
+        sim.distribute_souls();
+        Scenario { sim }
+    }
+
+    pub fn play(
+        &mut self,
+        actions: &[ActionType],
+    ) -> &mut Self {
+        for action in actions {
+            self.sim.step(action.clone());
+        }
+        self
+    }
+
+    pub fn wait(
+        &mut self,
+        turns: usize,
+    ) -> &mut Self {
+        for _ in 0..turns {
+            self.sim.step(ActionType::Nothing);
+        }
+        self
+    }
+
+    // Have the caster fire a Function straight at the target, then play out the turn it resolves in.
+    pub fn cast(
+        &mut self,
+        caster: Entity,
+        target: Entity,
+        function: Function,
+    ) -> &mut Self {
+        self.queue(caster, target, function);
+        self.wait(1)
+    }
+
+    // Like cast, but resolved along with everything else queued by the next turn.
+    pub fn queue(
+        &mut self,
+        caster: Entity,
+        target: Entity,
+        function: Function,
+    ) -> &mut Self {
+        let info = self.caster_info(caster);
+        self.sim.world.resource_mut::<WorldMap>().targeted_axioms.push((target, function, info));
+        self
+    }
+
+    // The creatures this Form would hit if the caster cast it right now.
+    pub fn form(
+        &mut self,
+        caster: Entity,
+        form: Form,
+    ) -> Vec<Entity> {
+        let info = self.caster_info(caster);
+        let mut query = self.sim.world.query::<(Entity, &Species, &Faction)>();
+        let census = take_census(query.iter(&self.sim.world));
+        grab_coords_from_form(&self.sim.world.resource::<WorldMap>().entities, form, info, &census).entities
+    }
+
+    pub fn set_momentum(
+        &mut self,
+        entity: Entity,
+        momentum: (i32, i32),
+    ) -> &mut Self {
+        self.sim.world.get_mut::<Position>(entity).expect("This creature has no Position!").momentum = momentum;
+        self
+    }
+
+    // Every creature of this kind, whatever the data it carries (EpsilonTail { order } matches any order).
+    pub fn find_all(
+        &mut self,
+        kind: &Species,
+    ) -> Vec<Entity> {
+        let mut query = self.sim.world.query::<(Entity, &Species)>();
+        query.iter(&self.sim.world).filter(|(_, species)| discriminant(*species) == discriminant(kind)).map(|(entity, _)| entity).collect()
+    }
+
+    pub fn find(
+        &mut self,
+        kind: &Species,
+    ) -> Entity {
+        *self.find_all(kind).first().unwrap_or_else(|| panic!("There is no {kind:?} in this scenario!"))
+    }
+
+    // The tangible creature standing on this tile.
+    pub fn at(
+        &self,
+        x: usize,
+        y: usize,
+    ) -> Option<Entity> {
+        self.sim.world.resource::<WorldMap>().entities[xy_idx(x, y)]
+    }
+
+    pub fn position(
+        &self,
+        entity: Entity,
+    ) -> (usize, usize) {
+        let pos = self.sim.world.get::<Position>(entity).expect("This creature has no Position!");
+        (pos.x, pos.y)
+    }
+
+    pub fn species(
+        &self,
+        entity: Entity,
+    ) -> Species {
+        self.sim.world.get::<Species>(entity).expect("This creature has no Species!").clone()
+    }
+
+    pub fn anchor(&mut self) -> Option<Entity> {
+        self.sim.player()
+    }
+
+    pub fn held(
+        &self,
+        entity: Entity,
+    ) -> usize {
+        self.breath(entity).held.len()
+    }
+
+    pub fn pile(
+        &self,
+        entity: Entity,
+    ) -> usize {
+        self.breath(entity).pile.iter().map(|caste| caste.len()).sum()
+    }
+
+    pub fn discard(
+        &self,
+        entity: Entity,
+    ) -> usize {
+        self.breath(entity).discard.iter().map(|caste| caste.len()).sum()
+    }
+
+    pub fn exhausted(
+        &self,
+        entity: Entity,
+    ) -> usize {
+        self.breath(entity).exhaust.len()
+    }
+
+    // Every soul still in play, wherever it sits.
+    pub fn souls(
+        &self,
+        entity: Entity,
+    ) -> usize {
+        self.held(entity) + self.pile(entity) + self.discard(entity)
+    }
+
+    pub fn set_hand(
+        &mut self,
+        entity: Entity,
+        hand_size: usize,
+        draw: DrawRule,
+    ) -> &mut Self {
+        let mut breath = self.sim.world.get_mut::<SoulBreath>(entity).expect("This creature has no SoulBreath!");
+        (breath.hand_size, breath.draw) = (hand_size, draw);
+        self
+    }
+
+    // Stacks of this virtue, or of the first effect of this kind, or 0 if the creature doesn't have it.
+    pub fn stacks(
+        &self,
+        entity: Entity,
+        kind: &EffectType,
+    ) -> usize {
+        if let Some(stacks) = self.sim.world.get::<Virtues>(entity).and_then(|virtues| virtues.get(kind)) { return stacks; }
+        let effects = self.sim.world.get::<AxiomEffects>(entity).expect("This creature has no AxiomEffects!");
+        effects.status.iter().find(|eff| discriminant(&eff.effect_type) == discriminant(kind)).map_or(0, |eff| eff.stacks)
+    }
+
+    pub fn set_stacks(
+        &mut self,
+        entity: Entity,
+        kind: &EffectType,
+        stacks: usize,
+    ) -> &mut Self {
+        if self.sim.world.get_mut::<Virtues>(entity).expect("This creature has no Virtues!").set(kind, stacks) { return self; }
+        let mut effects = self.sim.world.get_mut::<AxiomEffects>(entity).expect("This creature has no AxiomEffects!");
+        if let Some(eff) = effects.status.iter_mut().find(|eff| discriminant(&eff.effect_type) == discriminant(kind)) {
+            eff.stacks = stacks;
+        }
+        self
+    }
+
+    fn caster_info(
+        &mut self,
+        caster: Entity,
+    ) -> CasterInfo {
+        let is_player = self.anchor() == Some(caster);
+        let world = &self.sim.world;
+        let (pos, species) = (world.get::<Position>(caster).expect("The caster has no Position!"), world.get::<Species>(caster).expect("The caster has no Species!"));
+        let virtues = world.get::<Virtues>(caster).expect("The caster has no Virtues!");
+        let status = &world.get::<AxiomEffects>(caster).expect("The caster has no AxiomEffects!").status;
+        CasterInfo::new(caster, pos, species, virtues, status, is_player)
+    }
+
+    fn breath(
+        &self,
+        entity: Entity,
+    ) -> &SoulBreath {
+        self.sim.world.get::<SoulBreath>(entity).expect("This creature has no SoulBreath!")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{axiom::{AxiomEvent, AxiomLog, AxiomOutcome, AxiomTarget, Resolution, AXIOM_LOG_LENGTH, VIRTUE_CAP, Effect, TriggerType}, components::{Initiative, ReactiveAxioms, Wounded}, editor::{apply_soul_edit, EditError, EditPart, SoulEdit, SoulEditor}, harmony::{Harmony, CRITICAL_MASS}, soul::Soul};
+
+    const RIGHT: ActionType = ActionType::Walk { momentum: (1, 0) };
+
+    // A crate at the far end of a corridor from the player.
+    const CORRIDOR: &str = "
+##########
+#X......G#
+##########
+";
+
+    // A crate walled in on three sides, with the player around the corner.
+    const ALCOVE: &str = "
+#######
+#.....#
+#.#G#.#
+#..#..#
+#X....#
+#######
+";
+
+    // Play out the first turn, then hand back the GlamourCrate and the player.
+    fn crate_and_player(
+        vault: &str,
+    ) -> (Scenario, Entity, Entity) {
+        let mut scenario = Scenario::new(vault);
+        scenario.wait(1);
+        let (caster, player) = (scenario.find(&Species::GlamourCrate), scenario.anchor().unwrap());
+        (scenario, caster, player)
+    }
+
+    #[test]
+    fn epsilon_magnetizes_adjacent_tails() {
+        let mut scenario = Scenario::new("
+#########
+#.......#
+#.ECCC..#
+#.......#
+#X......#
+#########
+");
+        scenario.wait(1);
+        let head = scenario.find(&Species::EpsilonHead { len: 0 });
+        assert_eq!(scenario.species(head), Species::EpsilonHead { len: 3 });
+        let mut orders: Vec<i32> = scenario.find_all(&Species::EpsilonTail { order: 0 }).into_iter().map(|tail| match scenario.species(tail) {
+            Species::EpsilonTail { order } => order,
+            _ => unreachable!(),
+        }).collect();
+        orders.sort();
+        assert_eq!(orders, vec![0, 1, 2]);
+
+        scenario.wait(4);
+        // Each segment still clings to the one in front of it.
+        let tails = scenario.find_all(&Species::EpsilonTail { order: 0 });
+        let mut chain = vec![scenario.position(head)];
+        for order in 0..3 {
+            let tail = *tails.iter().find(|tail| scenario.species(**tail) == Species::EpsilonTail { order }).unwrap();
+            chain.push(scenario.position(tail));
+        }
+        for link in chain.windows(2) {
+            assert_eq!(link[0].0.abs_diff(link[1].0) + link[0].1.abs_diff(link[1].1), 1, "The snake came apart: {chain:?}");
+        }
+    }
+
+    #[test]
+    fn airlock_opens_when_walked_into() {
+        let mut scenario = Scenario::new("
+#####
+#X..>
+#####
+");
+        let airlock = scenario.find(&Species::Airlock { dir: 0 });
+        scenario.wait(1);
+        assert_eq!(scenario.at(4, 1), Some(airlock));
+        scenario.play(&[RIGHT, RIGHT, RIGHT]);
+        assert_eq!(scenario.at(4, 1), None);
+        assert!(scenario.stacks(airlock, &EffectType::OpenDoor) > 0);
+        assert_eq!(scenario.position(airlock), (4, 1));
+        scenario.wait(6); // Nobody is standing in the doorway, so it shuts once the effect runs out.
+        assert_eq!(scenario.stacks(airlock, &EffectType::OpenDoor), 0);
+        assert_eq!(scenario.at(4, 1), Some(airlock));
+    }
+
+    #[test]
+    fn coil_damage_scales_with_neighbours_and_pride() {
+        let (mut scenario, caster, player) = crate_and_player(ALCOVE);
+        assert_eq!(scenario.souls(player), 5);
+        scenario.cast(caster, player, Function::Coil); // 3 neighbours, 1 Pride.
+        assert_eq!(scenario.souls(player), 2);
+        assert_eq!(scenario.discard(caster), 3);
+
+        let (mut scenario, caster, player) = crate_and_player(ALCOVE);
+        scenario.set_stacks(caster, &EffectType::Pride, 2);
+        scenario.cast(caster, player, Function::Coil); // 3 neighbours, 2 Pride.
+        assert_eq!(scenario.souls(player), 0);
+        assert_eq!(scenario.held(player), 0);
+    }
+
+    #[test]
+    fn stolen_souls_change_hands_without_spawning_anything() {
+        let (mut scenario, caster, player) = crate_and_player("
+#####
+#XG.#
+#####
+");
+        let entities = scenario.sim.world.entities().len();
+        scenario.cast(caster, player, Function::FlatStealSouls { dam: 3 });
+        assert_eq!((scenario.souls(player), scenario.discard(caster)), (2, 3));
+        assert_eq!(scenario.sim.world.entities().len(), entities);
+    }
+
+    #[test]
+    fn composite_functions_branch_repeat_and_scale() {
+        let (mut scenario, caster, player) = crate_and_player(ALCOVE);
+        scenario.set_stacks(caster, &EffectType::Pride, 2);
+        let steal_one = Function::FlatStealSouls { dam: 1 };
+        scenario.cast(caster, player, Function::IfTargetHas { // The player is no door, so it takes the second branch.
+            effect: EffectType::OpenDoor,
+            then: Box::new(Function::Empty),
+            otherwise: Box::new(Function::ScaleBy { virtue: EffectType::Pride, of: Box::new(Function::Sequence(vec![steal_one.clone(), steal_one.clone()])) }),
+        });
+        assert_eq!(scenario.souls(player), 1);
+        scenario.cast(caster, player, Function::Repeat { times: 3, of: Box::new(steal_one) });
+        assert_eq!(scenario.souls(player), 0);
+    }
+
+    #[test]
+    fn reactions_fire_with_the_amount_of_their_trigger() {
+        let (mut scenario, caster, player) = crate_and_player(ALCOVE);
+        let mut reactive = ReactiveAxioms::default();
+        reactive.reactions.insert(TriggerType::TakeDamage, (Form::Ego, Function::ScaleByTrigger { of: Box::new(Function::Dash { dx: 1, dy: 0 }) }));
+        scenario.sim.world.entity_mut(player).insert(reactive);
+        scenario.cast(caster, player, Function::FlatStealSouls { dam: 2 }); // Flung one tile away per Soul lost.
+        assert_eq!(scenario.souls(player), 3);
+        assert_eq!(scenario.position(player), (3, 1));
+    }
+
+    #[test]
+    fn links_recast_every_turn_until_they_expire() {
+        let (mut scenario, caster, player) = crate_and_player(CORRIDOR);
+        let link = EffectType::Link { axiom: Box::new((Form::Ego, Function::Dash { dx: 1, dy: 0 })), targets: Vec::new() };
+        scenario.cast(caster, player, Function::ApplyEffect { effect: Effect { stacks: 3, effect_type: link.clone() } });
+        scenario.wait(5);
+        assert_eq!(scenario.position(player), (4, 1));
+        assert_eq!(scenario.stacks(player, &link), 0);
+    }
+
+    #[test]
+    fn reapplied_links_refresh_instead_of_stacking_up() {
+        let (mut scenario, caster, player) = crate_and_player(CORRIDOR);
+        let link = EffectType::Link { axiom: Box::new((Form::Ego, Function::Dash { dx: 1, dy: 0 })), targets: Vec::new() };
+        let apply = |stacks| Function::ApplyEffect { effect: Effect { stacks, effect_type: link.clone() } };
+        scenario.cast(caster, player, apply(9));
+        scenario.wait(1); // The Link has recast, and now remembers the player as its target.
+        scenario.cast(caster, player, apply(5));
+        let links = scenario.sim.world.get::<AxiomEffects>(player).unwrap().status.iter().filter(|eff| eff.effect_type.is_same_as(&link)).count();
+        assert_eq!(links, 1);
+        assert_eq!(scenario.stacks(player, &link), 4); // Refreshed to 5, then one turn went by.
+        // A Link recasting something else is its own effect.
+        let other = EffectType::Link { axiom: Box::new((Form::Ego, Function::Dash { dx: -1, dy: 0 })), targets: Vec::new() };
+        scenario.cast(caster, player, Function::ApplyEffect { effect: Effect { stacks: 2, effect_type: other } });
+        assert_eq!(scenario.sim.world.get::<AxiomEffects>(player).unwrap().status.len(), 2);
+    }
+
+    #[test]
+    fn effects_stack_by_their_definition() {
+        let (mut scenario, caster, player) = crate_and_player(CORRIDOR);
+        let apply = |stacks, effect_type: &EffectType| Function::ApplyEffect { effect: Effect { stacks, effect_type: effect_type.clone() } };
+        let link = EffectType::Link { axiom: Box::new((Form::Empty, Function::Empty)), targets: Vec::new() };
+        // Both land in the same turn, which then takes one stack off at its end.
+        for (effect_type, expected) in [(EffectType::Meltdown, 4), (EffectType::OpenDoor, 2), (link, 1), (EffectType::Possession { link: caster }, 2)] {
+            scenario.cast(caster, player, Function::Sequence(vec![apply(3, &effect_type), apply(2, &effect_type)]));
+            assert_eq!(scenario.stacks(player, &effect_type), expected);
+        }
+    }
+
+    #[test]
+    fn virtues_stay_between_their_minimum_and_cap() {
+        let (mut scenario, caster, player) = crate_and_player(CORRIDOR);
+        scenario.set_stacks(player, &EffectType::Discipline, 0);
+        assert_eq!(scenario.stacks(player, &EffectType::Discipline), 1);
+        scenario.cast(caster, player, Function::ApplyEffect { effect: Effect { stacks: 50, effect_type: EffectType::Grace } });
+        assert_eq!(scenario.stacks(player, &EffectType::Grace), VIRTUE_CAP);
+        assert!(scenario.sim.world.get::<AxiomEffects>(player).unwrap().status.is_empty());
+    }
+
+    #[test]
+    fn axioms_resolve_phase_by_phase_depth_or_breadth_first() {
+        // Which of these Functions the player resolved on themselves next turn, in order.
+        let resolved = |resolution: Resolution, casts: &[Function], watched: &[Function]| {
+            let mut scenario = Scenario::new("
+##########
+#X.......#
+##########
+");
+            scenario.wait(1);
+            scenario.sim.world.insert_resource(resolution);
+            let player = scenario.anchor().unwrap();
+            for function in casts {
+                scenario.queue(player, player, function.clone());
+            }
+            scenario.wait(1);
+            scenario.sim.world.resource::<AxiomLog>().events.iter()
+                .filter(|event| event.caster == player && event.target == AxiomTarget::Creature(player) && watched.contains(&event.function))
+                .map(|event| event.function.clone()).collect::<Vec<_>>()
+        };
+        let dash = |dx| Function::Dash { dx, dy: 0 };
+
+        // Queued latest phase first, resolved Movement, Collision, Damage, Trigger, then Expiry.
+        let by_phase = [dash(1), Function::BecomeTangible, Function::ExhaustSouls { num: 0 },
+            Function::ApplyEffect { effect: Effect { stacks: 1, effect_type: EffectType::Grace } }, Function::RefillHand];
+        let reversed: Vec<_> = by_phase.iter().rev().cloned().collect();
+        assert_eq!(resolved(Resolution::DepthFirst, &reversed, &by_phase), by_phase);
+
+        // The Sequence unpacks its Dashes right after itself, or behind the Dash already waiting.
+        let casts = [Function::Sequence(vec![dash(1), dash(2)]), dash(3)];
+        assert_eq!(resolved(Resolution::DepthFirst, &casts, &[dash(1), dash(2), dash(3)]), vec![dash(1), dash(2), dash(3)]);
+        assert_eq!(resolved(Resolution::BreadthFirst, &casts, &[dash(1), dash(2), dash(3)]), vec![dash(3), dash(1), dash(2)]);
+    }
+
+    #[test]
+    fn faster_creatures_act_more_often_and_turns_follow_the_player() {
+        let mut scenario = Scenario::new("
+#######
+#X.G.D#
+#######
+");
+        let (player, fast, slow) = (scenario.anchor().unwrap(), scenario.find(&Species::GlamourCrate), scenario.find(&Species::DisciplineCrate));
+        scenario.set_stacks(fast, &EffectType::Grace, VIRTUE_CAP); // 12 + 12 - 1 = 23 energy a tick.
+        scenario.set_stacks(slow, &EffectType::Discipline, 7); // 12 + 1 - 7 = 6 energy a tick.
+        let mut acted = Vec::new();
+        for _ in 0..24 {
+            scenario.sim.tick(ActionType::Nothing);
+            acted.push([player, fast, slow].map(|creature| scenario.sim.world.get::<Initiative>(creature).unwrap().ready));
+        }
+        // Everyone starts halfway to ACTION_COST, and spends it all on each action.
+        assert_eq!(acted[..4], [[true, true, false], [false, true, true], [true, true, false], [false, true, false]]);
+        let count = |i: usize| acted.iter().filter(|ready| ready[i]).count();
+        assert_eq!((count(0), count(1), count(2)), (12, 23, 6));
+        assert_eq!(scenario.sim.turns(), 12); // Only the player's actions count as turns.
+    }
+
+    #[test]
+    fn forms_pick_their_targets() {
+        let mut scenario = Scenario::new("
+#########
+#.......#
+#.G.P.D.#
+#.......#
+#X..G...#
+#########
+");
+        scenario.wait(1);
+        let player = scenario.anchor().unwrap();
+        let (glamour, axiom, discipline) = (scenario.at(2, 3).unwrap(), scenario.at(4, 3).unwrap(), scenario.at(6, 3).unwrap());
+        let other_glamour = scenario.at(4, 1).unwrap();
+        scenario.set_momentum(glamour, (1, 0));
+        assert_eq!(scenario.form(glamour, Form::MomentumBeam), vec![axiom]);
+        assert_eq!(scenario.form(glamour, Form::PiercingBeam { max_hits: 2 }), vec![axiom, discipline]);
+        assert!(scenario.form(glamour, Form::Cone { length: 1, spread: 1 }).is_empty());
+        assert_eq!(scenario.form(glamour, Form::Cone { length: 2, spread: 1 }), vec![axiom]);
+        assert!(scenario.form(discipline, Form::Ring { inner: 2, outer: 2 }).contains(&axiom));
+
+        // Each jump lands on the closest creature not hit yet, within range of the last one.
+        let chain = scenario.form(glamour, Form::Chain { bounces: 3, range: 2 });
+        assert_eq!(chain.len(), 4);
+        let mut from = scenario.position(glamour);
+        for link in &chain {
+            let to = scenario.position(*link);
+            assert!(from.0.abs_diff(to.0) + from.1.abs_diff(to.1) <= 2);
+            from = to;
+        }
+        assert!(!chain.contains(&glamour));
+
+        let mut crates = scenario.form(player, Form::AllOfSpecies { species: Box::new(Species::GlamourCrate) });
+        crates.sort();
+        let mut expected = vec![glamour, other_glamour];
+        expected.sort();
+        assert_eq!(crates, expected);
+        assert_eq!(scenario.form(player, Form::AllOfFaction { faction: Faction::Ordered }), vec![player]);
+
+        // The walls of the room hold the water in, and everything inside gets soaked.
+        let flooded = scenario.form(discipline, Form::Flood { radius: 20 });
+        assert!([glamour, axiom, other_glamour, player].iter().all(|crea| flooded.contains(crea)));
+        assert!(!flooded.contains(&discipline));
+        assert!(flooded.iter().all(|crea| (0..=8).contains(&scenario.position(*crea).0) && scenario.position(*crea).1 <= 5));
+    }
+
+    #[test]
+    fn crescent_arcs_grow_with_each_full_swipe() {
+        let mut scenario = Scenario::new("
+#####
+#.G.#
+#XGG#
+#####
+");
+        scenario.wait(1);
+        let player = scenario.anchor().unwrap();
+        scenario.sim.world.get_mut::<AxiomEffects>(player).unwrap().axioms = vec![(Form::CrescentArc, Function::Empty); 4];
+        scenario.set_momentum(player, (1, 0));
+        for hits in 1..=4 { // The front crate, the wall below it, the crate above it, then the floor under the player.
+            assert_eq!(scenario.form(player, Form::CrescentArc).len(), hits);
+            scenario.play(&[ActionType::SoulCast { slot: 0 }]);
+            assert_eq!(scenario.stacks(player, &EffectType::Crescent), hits);
+        }
+        assert_eq!(scenario.form(player, Form::CrescentArc).len(), 4); // The fifth tile, above the player, is empty.
+    }
+
+    #[test]
+    fn floor_functions_are_logged_and_make_way_first() {
+        let (mut scenario, caster, player) = crate_and_player(CORRIDOR);
+        let info = scenario.caster_info(caster);
+        for x in 2..8 {
+            scenario.sim.world.resource_mut::<WorldMap>().floor_axioms.push(((x, 1), Function::FlatStealSouls { dam: 1 }, info.clone()));
+        }
+        scenario.cast(caster, player, Function::FlatStealSouls { dam: 1 });
+        let fizzled = |event: &AxiomEvent| matches!(event.target, AxiomTarget::Tile(_)) && event.outcome == AxiomOutcome::Fizzled;
+        let others = |log: &AxiomLog| log.events.iter().filter(|event| !fizzled(event)).map(|event| (event.target.clone(), event.function.clone())).collect::<Vec<_>>();
+        let log = scenario.sim.world.resource::<AxiomLog>();
+        assert_eq!(log.events.iter().filter(|event| fizzled(event)).count(), 6);
+        let kept = others(log);
+        assert!(kept.contains(&(AxiomTarget::Creature(player), Function::FlatStealSouls { dam: 1 })));
+
+        // Once the log is full, those fizzles are the first to go.
+        for _ in 0..AXIOM_LOG_LENGTH {
+            scenario.sim.world.resource_mut::<WorldMap>().floor_axioms.push(((2, 1), Function::FlatStealSouls { dam: 1 }, info.clone()));
+        }
+        scenario.wait(1);
+        let log = scenario.sim.world.resource::<AxiomLog>();
+        assert_eq!(log.events.len(), AXIOM_LOG_LENGTH);
+        assert!(others(log).starts_with(&kept));
+    }
+
+    #[test]
+    fn the_soul_deck_draws_shuffles_and_exhausts() {
+        let vault = "
+###
+#X#
+###
+";
+        let mut scenario = Scenario::new(vault);
+        scenario.wait(1);
+        let player = scenario.anchor().unwrap();
+        scenario.sim.world.get_mut::<AxiomEffects>(player).unwrap().axioms = vec![(Form::Empty, Function::Empty); 4];
+        assert_eq!((scenario.held(player), scenario.pile(player), scenario.discard(player)), (4, 1, 0));
+        scenario.play(&[ActionType::SoulCast { slot: 0 }]); // Replaced straight from the pile.
+        assert_eq!((scenario.held(player), scenario.pile(player), scenario.discard(player)), (4, 0, 1));
+        scenario.play(&[ActionType::SoulCast { slot: 0 }]); // The pile is empty, so the discard is shuffled back in first.
+        assert_eq!((scenario.held(player), scenario.pile(player), scenario.discard(player)), (4, 1, 0));
+        let shuffled = |event: &AxiomEvent| event.function == Function::ShuffleSouls && event.outcome == AxiomOutcome::Applied;
+        assert!(scenario.sim.world.resource::<AxiomLog>().events.iter().any(shuffled));
+        scenario.play(&[ActionType::SoulCast { slot: 0 }]);
+        scenario.cast(player, player, Function::ExhaustSouls { num: 3 }); // Only one soul in the discard to take.
+        assert_eq!((scenario.held(player), scenario.discard(player), scenario.exhausted(player)), (4, 0, 1));
+        assert_eq!(scenario.souls(player), 4);
+
+        let mut scenario = Scenario::new(vault);
+        scenario.wait(1);
+        let player = scenario.anchor().unwrap();
+        scenario.sim.world.get_mut::<AxiomEffects>(player).unwrap().axioms = vec![(Form::Empty, Function::Empty); 4];
+        scenario.set_hand(player, 6, DrawRule::OnTurn);
+        scenario.wait(1); // Only one soul left to draw.
+        assert_eq!((scenario.held(player), scenario.pile(player)), (5, 0));
+        scenario.play(&[ActionType::SoulCast { slot: 0 }]); // Discarded, then shuffled and drawn back once the turn is over.
+        assert_eq!((scenario.held(player), scenario.pile(player), scenario.discard(player)), (5, 0, 0));
+    }
+
+    #[test]
+    fn harmony_warns_then_converts_everything_at_critical_mass() {
+        let mut scenario = Scenario::new("
+###################
+#X#X#X#X#X#X#X#X#X#
+###################
+");
+        scenario.wait(1);
+        let terminals = scenario.find_all(&Species::Terminal);
+        let harmonize = |scenario: &mut Scenario, creatures: &[Entity]| { // Every soul of these creatures turns Serene.
+            for creature in creatures {
+                let souls = scenario.souls(*creature);
+                let mut breath = scenario.sim.world.get_mut::<SoulBreath>(*creature).unwrap();
+                let held = breath.held.len();
+                breath.held = vec![Soul::Serene; held];
+                (breath.pile, breath.discard) = (vec![Vec::new(); 5], vec![Vec::new(); 5]);
+                breath.pile[0] = vec![Soul::Serene; souls - held];
+            }
+            scenario.wait(1);
+        };
+        harmonize(&mut scenario, &terminals[..4]); // 5 souls each.
+        let harmony = scenario.sim.world.resource::<Harmony>();
+        assert_eq!((harmony.serene, harmony.warned, harmony.critical), (CRITICAL_MASS / 2, 1, false));
+        harmonize(&mut scenario, &terminals[4..8]);
+        assert!(scenario.sim.world.resource::<Harmony>().critical);
+        let mut factions = scenario.sim.world.query::<&Faction>();
+        assert!(factions.iter(&scenario.sim.world).all(|faction| faction == &Faction::Serene));
+    }
+
+    #[test]
+    fn swap_anchor_hands_over_the_reality_anchor() {
+        let mut scenario = Scenario::new("
+#####
+#X#P#
+#####
+");
+        scenario.wait(1);
+        let (player, crate_) = (scenario.anchor().unwrap(), scenario.find(&Species::AxiomCrate));
+        scenario.cast(player, crate_, Function::SwapAnchor);
+        assert_eq!(scenario.anchor(), Some(crate_));
+    }
+
+    #[test]
+    fn crates_pushed_into_activators_rewrite_axioms() {
+        let mut scenario = Scenario::new("
+#######
+#X!0A.#
+#######
+");
+        scenario.wait(1);
+        let (player, crate_, nurse) = (scenario.anchor().unwrap(), scenario.at(2, 1).unwrap(), scenario.find(&Species::ChromeNurse));
+        let slot = |scenario: &Scenario| scenario.sim.world.get::<AxiomEffects>(nurse).unwrap().axioms[0].0.clone();
+        let before = slot(&scenario);
+        scenario.set_momentum(player, (1, 0)); // Pushes go the way the pusher was already heading.
+        scenario.play(&[RIGHT]);
+        assert_eq!(slot(&scenario), Form::Ego);
+        assert_eq!(scenario.species(crate_), Species::FormCrate { form: before.clone() });
+        assert_eq!(scenario.position(player), (1, 1));
+
+        // The crate now carries the old Form, so a second push puts it back.
+        scenario.set_momentum(player, (1, 0));
+        scenario.play(&[RIGHT]);
+        assert_eq!(slot(&scenario), before);
+        assert_eq!(scenario.species(crate_), Species::FormCrate { form: Form::Ego });
+    }
+
+    #[test]
+    fn soul_edits_are_validated_then_swap_between_captured_creatures() {
+        let mut scenario = Scenario::new("
+#######
+#X.A.A#
+#######
+");
+        scenario.wait(1);
+        let (player, captive, free) = (scenario.anchor().unwrap(), scenario.at(3, 1).unwrap(), scenario.at(5, 1).unwrap());
+        scenario.sim.world.entity_mut(captive).insert(Wounded);
+        let swap = |from, to| SoulEdit { part: EditPart::Function, from, to };
+        let function = |scenario: &Scenario, entity: Entity, slot: usize| scenario.sim.world.get::<AxiomEffects>(entity).unwrap().axioms[slot].1.clone();
+        assert_eq!(apply_soul_edit(&mut scenario.sim.world, &swap((player, 0), (captive, 0))), Err(EditError::Locked));
+
+        scenario.sim.world.insert_resource(SoulEditor { unlocked: true, ..default() });
+        assert_eq!(apply_soul_edit(&mut scenario.sim.world, &swap((player, 0), (free, 0))), Err(EditError::NotCaptured(free)));
+        assert_eq!(apply_soul_edit(&mut scenario.sim.world, &swap((player, 0), (captive, 7))), Err(EditError::NoSuchSlot(captive, 7)));
+        assert_eq!(apply_soul_edit(&mut scenario.sim.world, &swap((player, 2), (player, 2))), Err(EditError::SameSlot));
+
+        let (mine, theirs) = (function(&scenario, player, 0), function(&scenario, captive, 1));
+        assert_eq!(apply_soul_edit(&mut scenario.sim.world, &swap((player, 0), (captive, 1))), Ok(()));
+        assert_eq!((function(&scenario, player, 0), function(&scenario, captive, 1)), (theirs, mine));
+        assert_eq!(scenario.sim.world.resource::<SoulEditor>().history.len(), 1);
+    }
+}
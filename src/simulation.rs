@@ -0,0 +1,135 @@
+use bevy::{ecs::schedule::ScheduleLabel, prelude::*};
+
+use crate::{rng::GameRng, axiom::{AxiomEvent, AxiomLog, Resolution}, harmony::Harmony, components::{Initiative, Intangible, QueuedAction, RealityAnchor, Virtues}, input::ActionType, replay::{Replay, ReplayRecorder}, map::WorldMap, soul::{distribute_some_souls, CurrentEntityInUI}, species::{is_intangible, CreatureBundle, Species}, turn::{turn_systems, TurnCount}, ui::{LogMessage, LogNotice}, world::{CurrentPlane, Plane}, TurnState, ZoomInEffect};
+
+/*
+
+The rules of the game, without a window, assets or animations.
+
+The systems in turn_systems only ever touch plain data (Position, SoulBreath, AxiomEffects, WorldMap...),
+and anything they want shown on screen is pushed to WorldMap.anim_queue. The game runs that chain once per
+turn and mirrors the results with sprites and tweens. A Simulation runs it on its own World instead, so
+whole battles can be scripted and checked on a machine with no GPU.
+
+*/
+
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+struct SimulateTurn;
+
+pub struct Simulation {
+    pub world: World,
+    turn: Schedule,
+}
+
+impl Simulation {
+    pub fn new(
+        seed: u64,
+    ) -> Self {
+        let mut world = World::new();
+        world.insert_resource(GameRng::new(seed));
+        world.init_resource::<WorldMap>();
+        world.init_resource::<Events<LogMessage>>();
+        world.init_resource::<Events<LogNotice>>();
+        world.init_resource::<Events<AxiomEvent>>();
+        world.init_resource::<AxiomLog>();
+        world.init_resource::<Resolution>();
+        world.init_resource::<Harmony>();
+        world.init_resource::<ReplayRecorder>();
+        world.init_resource::<NextState<TurnState>>();
+        world.insert_resource(TurnCount{turns: 0});
+        world.insert_resource(CurrentPlane{plane: Plane::WorldStem});
+        world.insert_resource(CurrentEntityInUI{entity: Entity::PLACEHOLDER});
+        let mut zoom = ZoomInEffect{timer: Timer::from_seconds(0.5, TimerMode::Once), destination: Plane::Epsilon};
+        zoom.timer.pause();
+        world.insert_resource(zoom);
+
+        let mut turn = Schedule::new(SimulateTurn);
+        turn.add_systems(turn_systems());
+        Simulation { world, turn }
+    }
+
+    pub fn spawn_creature(
+        &mut self,
+        species: Species,
+        x: usize,
+        y: usize,
+    ) -> Entity {
+        let entity_id = self.world.spawn(CreatureBundle::new().with_data(x, y, species.clone())).id();
+        if is_intangible(&species){
+            self.world.entity_mut(entity_id).insert(Intangible);
+        }
+        entity_id
+    }
+
+    pub fn spawn_player(
+        &mut self,
+        species: Species,
+        x: usize,
+        y: usize,
+    ) -> Entity {
+        let entity_id = self.spawn_creature(species, x, y);
+        self.world.entity_mut(entity_id).insert(RealityAnchor { player_id: 0 });
+        self.world.resource_mut::<CurrentEntityInUI>().entity = entity_id;
+        entity_id
+    }
+
+    // Hand out the starting souls, like the game does once everything has been spawned.
+    pub fn distribute_souls(&mut self) {
+        let mut schedule = Schedule::default();
+        schedule.add_systems(distribute_some_souls);
+        schedule.run(&mut self.world);
+    }
+
+    // Play one full turn, with the player (if any) taking the given action.
+    // Ticks where the player is too slow to act are played out first, like the game does on its own.
+    pub fn step(
+        &mut self,
+        action: ActionType,
+    ) {
+        while !self.player_acts_next() {
+            self.tick(ActionType::Nothing);
+        }
+        self.tick(action);
+    }
+
+    // Play a single tick of initiative. The player's action only happens if they have the energy for it.
+    pub fn tick(
+        &mut self,
+        action: ActionType,
+    ) {
+        let mut players = self.world.query_filtered::<&mut QueuedAction, With<RealityAnchor>>();
+        for mut queued in players.iter_mut(&mut self.world) {
+            queued.action = action.clone();
+        }
+        self.turn.run(&mut self.world);
+        self.world.resource_mut::<WorldMap>().anim_queue.clear(); // Nobody is watching.
+        self.world.resource_mut::<Events<LogMessage>>().update();
+        self.world.resource_mut::<Events<LogNotice>>().update();
+        self.world.resource_mut::<Events<AxiomEvent>>().update();
+    }
+
+    // Play back a recorded run. The Simulation should have been created with the replay's seed.
+    pub fn play(
+        &mut self,
+        replay: &Replay,
+    ) {
+        for action in &replay.actions {
+            self.step(action.clone());
+        }
+    }
+
+    // Without a player, every tick is a full turn.
+    pub fn player_acts_next(&mut self) -> bool {
+        let mut players = self.world.query_filtered::<(&Initiative, &Species, &Virtues), With<RealityAnchor>>();
+        players.iter(&self.world).next().map(|(initiative, species, virtues)| initiative.acts_next(species, virtues)).unwrap_or(true)
+    }
+
+    pub fn player(&mut self) -> Option<Entity> {
+        let mut players = self.world.query_filtered::<Entity, With<RealityAnchor>>();
+        players.iter(&self.world).next()
+    }
+
+    pub fn turns(&self) -> usize {
+        self.world.resource::<TurnCount>().turns
+    }
+}
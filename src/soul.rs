@@ -1,22 +1,34 @@
 use std::{f32::consts::PI, time::Duration};
 
 use bevy::prelude::*;
-use bevy_tweening::{Animator, Tween, EaseFunction, lens::TransformPositionLens};
+use bevy_tweening::{Animator, Tween, EaseFunction, Tracks, lens::{TransformPositionLens, TransformScaleLens}};
 use rand::Rng;
+use serde::{Deserialize, Serialize};
 
-use crate::{SpriteSheetHandle, components::{SoulBreath, Position, MomentumMarker}, ui::CenterOfWheel, species::{is_soulless, Species}};
+use crate::{rng::GameRng, SpriteSheetHandle, components::{SoulBreath, Position, MomentumMarker}, ui::CenterOfWheel, species::{is_soulless, Species}};
+
+/*
+
+Souls are plain values, kept in each creature's SoulBreath. Only the creature shown in CurrentEntityInUI
+gets sprites for them, on the soul wheel. Every frame, soul_rotation lines the SoulSprites up with where that
+creature's souls are. Sprites that are already in the right spot stay there, the ones left over are handed to
+the souls that moved (of the same caste first, so a cast soul glides from the hand to the discard), and the
+wheel spawns or despawns sprites to make up the difference. Souls drained from one creature to another on
+the map get a FlyingSoul sprite of their own, gone once it lands.
+
+*/
 
 pub struct SoulPlugin;
 
 impl Plugin for SoulPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(PostStartup, distribute_some_souls);
-        app.add_systems(Update, soul_rotation);
+        app.add_systems(Update, (soul_rotation, land_flying_souls));
         app.insert_resource(SoulRotationTimer{timer: Timer::new(Duration::from_millis(10000), TimerMode::Repeating)});
     }
 }
 
-#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Soul {
     Feral,
     Ordered,
@@ -25,14 +37,26 @@ pub enum Soul {
     Serene,
 }
 
-#[derive(Bundle)]
-pub struct SoulBundle {
-    sprite_bundle: SpriteSheetBundle,
-    animation: Animator<Transform>,
-    name: Name,
-    soul: Soul,
+// Where a soul was last drawn on the wheel. When its SoulBreath moves it elsewhere, it glides to its new spot.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SoulZone {
+    Unplaced,
+    Pile,
+    Held { slot: usize },
+    Discard,
 }
 
+// A soul of the creature shown in the UI, drawn on the wheel.
+#[derive(Component)]
+pub struct SoulSprite {
+    pub soul: Soul,
+    pub zone: SoulZone,
+    pub index: usize, // Its place in its caste's pile or discard, or its slot in the hand.
+}
+
+#[derive(Component)]
+pub struct FlyingSoul;
+
 #[derive(Resource)]
 pub struct CurrentEntityInUI {
     pub entity: Entity,
@@ -43,21 +67,63 @@ pub struct SoulRotationTimer {
     pub timer: Timer
 }
 
+fn soul_bundle(
+    texture_atlas_handle: &SpriteSheetHandle,
+    soul: Soul,
+    start: Vec3,
+) -> SpriteSheetBundle {
+    SpriteSheetBundle {
+        texture_atlas: texture_atlas_handle.handle.clone(),
+        sprite: TextureAtlasSprite{
+            index : match_soul_with_sprite(&soul),
+            custom_size: Some(Vec2::new(0.25, 0.25)),
+            ..default()
+        },
+        transform: Transform::from_translation(start),
+        ..default()
+    }
+}
+
+// A soul crossing the map from one creature to another.
+pub fn spawn_flying_soul(
+    commands: &mut Commands,
+    texture_atlas_handle: &SpriteSheetHandle,
+    soul: Soul,
+    start: Vec3,
+    end: Vec3,
+) {
+    let tween = Tween::new(
+        EaseFunction::QuadraticInOut,
+        Duration::from_millis(500),
+        TransformPositionLens { start, end },
+    );
+    commands.spawn((soul_bundle(texture_atlas_handle, soul, start), Animator::new(tween), FlyingSoul, Name::new("Flying Soul")));
+}
+
+fn land_flying_souls(
+    flying: Query<(Entity, &Animator<Transform>), With<FlyingSoul>>,
+    mut commands: Commands,
+) {
+    for (entity, anim) in flying.iter() {
+        if anim.tweenable().progress() == 1.0 {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
 fn soul_rotation(
     ui_center: Res<CenterOfWheel>,
     current: Res<CurrentEntityInUI>,
     query: Query<(&SoulBreath, &Position)>,
-    mut soul: Query<(&mut Transform, &mut Visibility, &Animator<Transform>, &Soul)>,
+    mut sprites: Query<(Entity, &mut Transform, &mut Animator<Transform>, &mut TextureAtlasSprite, &mut SoulSprite), Without<MomentumMarker>>,
     mut time: ResMut<SoulRotationTimer>,
-    mut momentum_mark: Query<(&mut TextureAtlasSprite, &MomentumMarker)>,
+    mut momentum_mark: Query<(&mut TextureAtlasSprite, &MomentumMarker), Without<SoulSprite>>,
     epoch: Res<Time>,
+    mut commands: Commands,
+    texture_atlas_handle: Res<SpriteSheetHandle>,
 ){
     time.timer.tick(epoch.delta());
-    for (_t, mut vis, anim, _s) in soul.iter_mut(){
-        if anim.tweenable().progress() != 1.0 { continue; }
-        *vis = Visibility::Hidden;
-    }
-    let (draw, held, disc, momentum) = if let Ok((breath, pos)) = query.get(current.entity) { (&breath.pile, &breath.held, &breath.discard, pos.momentum) } 
+    let (draw, held, disc, hand_size, momentum) = if let Ok((breath, pos)) = query.get(current.entity) { (&breath.pile, &breath.held, &breath.discard, breath.hand_size, pos.momentum) } 
     else{ panic!("The entity meant to be represented in the UI doesn't have a SoulBreath component!")};
     for (mut sprite, mom) in momentum_mark.iter_mut(){
         if mom.dir == momentum {
@@ -67,64 +133,106 @@ fn soul_rotation(
             sprite.index = 58;
         }
     }
+    let mut placements = Vec::new();
     for j in draw.iter() {
-        for (caste, i) in j.iter().enumerate(){
-            if let Ok((mut trans, mut vis, anim, soul_type)) = soul.get_mut(*i) {
-                *vis = Visibility::Visible;
-                let index = draw[match_soul_with_display_index(soul_type)].iter().position(|&ent| ent == *i);
-                let index = match index {
-                    Some(ind) => ind,
-                    None => {
-                        dbg!(caste);
-                        dbg!(&soul_type);
-                        dbg!(match_soul_with_display_index(soul_type));
-                        panic!("The desired soul in the draw pile was not found.");
-                    }
-                };
-                if anim.tweenable().progress() != 1.0 { continue; }
-                (trans.translation.x, trans.translation.y) = get_soul_rot_position(soul_type, (ui_center.x, ui_center.y), false, time.timer.elapsed_secs(), index);
-                trans.scale = Vec3{ x: 1., y: 1., z: 0.};
-            }
-            else{ panic!("A soul in the draw pile has no UIElement component!")};
+        for (index, i) in j.iter().enumerate(){
+            placements.push((*i, SoulZone::Pile, index));
         }
     }
-    for i in held.iter().enumerate(){
-        let slot_coords_ui = [
-            ((3.*PI/4.).cos() * 1.5 +ui_center.x, (3.*PI/4.).sin() * 1.5 +ui_center.y),
-            ((1.*PI/4.).cos() * 1.5 +ui_center.x, (1.*PI/4.).sin() * 1.5 +ui_center.y),
-            ((5.*PI/4.).cos() * 1.5 +ui_center.x, (5.*PI/4.).sin() * 1.5 +ui_center.y),
-            ((7.*PI/4.).cos() * 1.5 +ui_center.x, (7.*PI/4.).sin() * 1.5 +ui_center.y)
-        ];
-        if let Ok((mut trans, mut vis, anim, _soul_type)) = soul.get_mut(*i.1) { 
-            *vis = Visibility::Visible;
-            if anim.tweenable().progress() != 1.0 { continue; }
-            (trans.translation.x, trans.translation.y) = slot_coords_ui[i.0];
-            trans.scale = Vec3{ x: 2., y: 2., z: 0.};
-        }
-        else{ panic!("A soul in the draw pile has no UIElement component!")};
+    for (slot, i) in held.iter().enumerate(){
+        placements.push((*i, SoulZone::Held { slot }, slot));
     }
     for j in disc.iter() {
-        for (caste, i) in j.iter().enumerate(){
-            if let Ok((mut trans, mut vis, anim, soul_type)) = soul.get_mut(*i) { 
-                *vis = Visibility::Visible;
-                let index = disc[match_soul_with_display_index(soul_type)].iter().position(|&ent| ent == *i);
-                let index = match index {
-                    Some(ind) => ind,
-                    None => {
-                        dbg!(caste);
-                        dbg!(&soul_type);
-                        dbg!(current.entity);
-                        dbg!(match_soul_with_display_index(soul_type));
-                        panic!("The desired soul in the discard pile was not found.");
-                    }
-                };
-                if anim.tweenable().progress() != 1.0 { continue; }
-                (trans.translation.x, trans.translation.y) = get_soul_rot_position(soul_type, (ui_center.x, ui_center.y), true, time.timer.elapsed_secs(), index);
-                trans.scale = Vec3{ x: 1., y: 1., z: 0.};
+        for (index, i) in j.iter().enumerate(){
+            placements.push((*i, SoulZone::Discard, index));
+        }
+    }
+    // Sprites already showing a soul in its spot keep it. The others are handed out to the souls that moved.
+    let mut idle: Vec<(Entity, Soul)> = sprites.iter().map(|(entity, _, _, _, shown)| (entity, shown.soul)).collect();
+    let mut assigned = Vec::with_capacity(placements.len());
+    let mut moved = Vec::new();
+    for placement in placements {
+        let in_place = idle.iter().position(|(entity, _)| sprites.get(*entity).is_ok_and(|(_, _, _, _, shown)| (shown.soul, shown.zone, shown.index) == placement));
+        match in_place {
+            Some(found) => assigned.push((idle.swap_remove(found).0, placement)),
+            None => moved.push(placement),
+        }
+    }
+    for placement in moved {
+        let same_caste = idle.iter().position(|(_, soul)| *soul == placement.0);
+        match same_caste.or(if idle.is_empty() { None } else { Some(0) }) {
+            Some(found) => assigned.push((idle.swap_remove(found).0, placement)),
+            None => { // It will be placed next frame, like the others.
+                let (soul, _zone, index) = placement;
+                let center = Vec3::new(ui_center.x, ui_center.y, 0.5);
+                let tween = Tween::new(EaseFunction::QuadraticInOut, Duration::from_millis(1), TransformPositionLens { start: center, end: center });
+                commands.spawn((soul_bundle(&texture_atlas_handle, soul, center), Animator::new(tween),
+                    SoulSprite { soul, zone: SoulZone::Unplaced, index }, Name::new("Breathed Soul")));
             }
-            else{ panic!("A soul in the draw pile has no UIElement component!")};
         }
     }
+    for (entity, _soul) in idle {
+        commands.entity(entity).despawn();
+    }
+    for (entity, (soul_type, zone, index)) in assigned {
+        let Ok((_entity, mut trans, mut anim, mut sprite, mut shown)) = sprites.get_mut(entity) else { continue };
+        if shown.soul != soul_type { // Souls can be harmonized into Serene ones, and sprites change hands.
+            shown.soul = soul_type;
+            sprite.index = match_soul_with_sprite(&soul_type);
+        }
+        let moved = shown.zone != zone || shown.index != index;
+        let lookahead = if moved { 0.5 } else { 0. }; // Aim where the wheel will be once the glide is over.
+        let (place, scale) = match zone {
+            SoulZone::Held { slot } => (get_held_soul_position(slot, hand_size, (ui_center.x, ui_center.y)), 2.),
+            SoulZone::Discard => (get_soul_rot_position(&soul_type, (ui_center.x, ui_center.y), true, time.timer.elapsed_secs()+lookahead, index), 1.),
+            _ => (get_soul_rot_position(&soul_type, (ui_center.x, ui_center.y), false, time.timer.elapsed_secs()+lookahead, index), 1.),
+        };
+        if moved {
+            (shown.zone, shown.index) = (zone, index);
+            let tween_tr = Tween::new(
+                EaseFunction::QuadraticInOut,
+                Duration::from_millis(500),
+                TransformPositionLens {
+                    start: trans.translation,
+                    end: Vec3{ x: place.0, y: place.1, z: 0.5},
+                },
+            );
+            let tween_sc = Tween::new(
+                EaseFunction::QuadraticInOut,
+                Duration::from_millis(500),
+                TransformScaleLens {
+                    start: trans.scale,
+                    end: Vec3{ x: scale, y: scale, z: 0.},
+                },
+            );
+            anim.set_tweenable(Tracks::new([tween_tr, tween_sc]));
+            continue;
+        }
+        if anim.tweenable().progress() != 1.0 { continue; }
+        (trans.translation.x, trans.translation.y) = place;
+        trans.scale = Vec3{ x: scale, y: scale, z: 0.};
+    }
+}
+
+// Held souls sit in pairs down the inside of the wheel, left then right, like the keys that cast them.
+// A lone last soul gets the bottom of the wheel to itself.
+pub fn get_held_soul_position(
+    slot: usize,
+    hand_size: usize,
+    ui_center: (f32, f32),
+) -> (f32, f32){
+    let rows = hand_size.max(1).div_ceil(2);
+    let row = slot / 2;
+    let angle = if slot + 1 == hand_size && hand_size % 2 == 1 {
+        3.*PI/2.
+    } else {
+        let left = PI/2. + (row as f32 + 0.5) * PI / rows as f32;
+        if slot % 2 == 1 { PI - left } else { left }
+    };
+    (
+        angle.cos() * 1.5 +ui_center.0,
+        angle.sin() * 1.5 +ui_center.1,
+    )
 }
 
 pub fn get_soul_rot_position(
@@ -151,60 +259,22 @@ pub fn get_soul_rot_position(
     )
 }
 
-fn distribute_some_souls(
-    mut commands: Commands,
-    texture_atlas_handle: Res<SpriteSheetHandle>,
+pub fn distribute_some_souls(
     mut creatures: Query<(&Species, &mut SoulBreath)>,
+    mut rng: ResMut<GameRng>,
 ){  for (sp, mut breath) in creatures.iter_mut(){
         if is_soulless(sp) {
             breath.soulless = true;
             continue;
         }
-        for i in 0..5{
-            let soul = vec![Soul::Serene, Soul::Feral, Soul::Ordered, Soul::Saintly, Soul::Vile];
-            let tween = Tween::new(
-                EaseFunction::QuadraticInOut,
-                Duration::from_millis(1000),
-                TransformPositionLens {
-                    start: Vec3::ZERO,
-                    end: Vec3{ x: 0., y: 0., z: 0.5},
-                },
-            );
-            let scale = if i < 4 {
-                Vec3::new(3., 3., 0.)
-            } else { Vec3::new(1., 1., 0.) };
-            let mut rng = rand::thread_rng();
-            let index = rng.gen_range(1..5);
-            let entity = commands.spawn(SoulBundle{
-                sprite_bundle: SpriteSheetBundle {
-                    texture_atlas: texture_atlas_handle.handle.clone(),
-                    sprite: TextureAtlasSprite{
-                        index : match_soul_with_sprite(&soul[index]),
-                        custom_size: Some(Vec2::new(0.25, 0.25)),
-                        ..default()
-                    },
-                    visibility: Visibility::Hidden,
-                    transform: Transform {
-                        translation: Vec3::ZERO,
-                        scale,
-                        ..default()
-                    },
-                    ..default()
-                },
-                animation: Animator::new(tween),
-                name: Name::new("Breathed Soul"),
-                soul: soul[index].clone(),
-            }).id();
-            if i < 4 {
-                breath.held.push(entity);
-            }
-            else if i < 12 {
-                breath.pile[index].push(entity);
-            }
-            else {
-                breath.discard[index].push(entity);
-            }
+        let souls = [Soul::Serene, Soul::Feral, Soul::Ordered, Soul::Saintly, Soul::Vile];
+        for _ in 0..breath.missing() {
+            let index = rng.souls.gen_range(1..5); // Never Serene.
+            breath.held.push(souls[index]);
         }
+        // One more in the pile, to replace the first soul cast.
+        let index = rng.souls.gen_range(1..5);
+        breath.pile[index].push(souls[index]);
     }
 }
 
@@ -232,17 +302,17 @@ pub fn match_soul_with_display_index(
     }
 }
 
-pub fn select_random_entities<R: Rng>(pools: &mut Vec<Vec<Entity>>, dam: usize, rng: &mut R) -> Vec<Entity> {
-    let mut selected_entities = Vec::new();
+pub fn select_random_souls<R: Rng>(pools: &mut [Vec<Soul>], dam: usize, rng: &mut R) -> Vec<Soul> {
+    let mut selected_souls = Vec::new();
     let mut pool_indices: Vec<usize> = (0..pools.len()).collect(); // Get the list of potential slots to take from.
-    while selected_entities.len() < dam && !pool_indices.is_empty() {
+    while selected_souls.len() < dam && !pool_indices.is_empty() {
         let pool_index = rng.gen_range(0..pool_indices.len());
         let pool = &mut pools[pool_indices[pool_index]];
-        if let Some(entity) = pool.pop() {
-            selected_entities.push(entity);
+        if let Some(soul) = pool.pop() {
+            selected_souls.push(soul);
         } else {
             pool_indices.remove(pool_index);
         }
     }
-    selected_entities
+    selected_souls
  }
\ No newline at end of file
@@ -1,130 +1,127 @@
-use std::time::Duration;
+use std::{collections::HashMap, fs, sync::OnceLock, time::Duration};
 
-use crate::{components::{Position, QueuedAction, SoulBreath, AxiomEffects, Faction, Thought}, SpriteSheetHandle, input::ActionType, axiom::{Form, Function, Effect, EffectType, match_form_with_name}};
+use crate::{components::{Position, QueuedAction, SoulBreath, AxiomEffects, DrawRule, Faction, Thought, Initiative, ReactiveAxioms, Virtues, DEFAULT_HAND_SIZE, MAX_HAND_SIZE}, SpriteSheetHandle, dsl::parse_axioms, input::ActionType, axiom::{Form, Function, TriggerType}};
 use bevy::prelude::*;
 use bevy_tweening::{*, lens::TransformPositionLens};
+use serde::{Deserialize, Serialize};
 use std::f32::consts::PI;
 
+#[derive(Deserialize, Default)]
 pub enum MapColour {
+    #[default]
     White,
     Plum,
 }
 
 #[derive(Bundle)]
-pub struct CreatureBundle {
-    sprite_bundle: SpriteSheetBundle,
-    animation: Animator<Transform>,
+pub struct CreatureBundle { // Everything the rules need to know about a creature, without any visuals.
     name: Name,
     species: Species,
     position: Position,
     action: QueuedAction,
     breath: SoulBreath,
     axioms: AxiomEffects,
+    virtues: Virtues,
     faction: Faction,
     thought: Thought,
+    initiative: Initiative,
+    reactions: ReactiveAxioms,
 }
 
-impl CreatureBundle { // Creatures displayed on screen.
-    pub fn new(
-        tex_handle: &SpriteSheetHandle
-    ) -> Self {
-        let texture_atlas_handle = &tex_handle.handle;
-        let mut lots_of_vec = Vec::new();
-        for _i in 0..5 {lots_of_vec.push(Vec::new())}
-        let tween = Tween::new(
-            EaseFunction::QuadraticInOut,
-            Duration::from_millis(1000),
-            TransformPositionLens {
-                start: Vec3::ZERO,
-                end: Vec3::ZERO,
-            },
-        );
+impl CreatureBundle {
+    pub fn new() -> Self {
         Self{
-            sprite_bundle : SpriteSheetBundle {
-                texture_atlas: texture_atlas_handle.clone(),
-                sprite: TextureAtlasSprite{
-                    index : 0_usize,
-                    custom_size: Some(Vec2::new(1., 1.)),
-                    ..default()
-                },
-                transform: Transform {
-                    translation: Vec3{ x: 0., y: 0., z: 0.0},
-                    scale: Vec3{x: 0.5, y: 0.5, z:1.},
-                    
-                    ..default()
-                },
-                ..default()
-            },
-            animation: Animator::new(tween),
             name: Name::new("Bugged Creature"),
             species: Species::BuggedSpecies,
             position: Position { x: 0, y: 0, ox: 0, oy: 0, momentum: (-1, 0)},
             action: QueuedAction { action: ActionType::Nothing},
-            breath: SoulBreath { pile: lots_of_vec.clone(), discard: lots_of_vec.clone(), held: Vec::new(), soulless: false},
+            breath: SoulBreath::default(),
             faction: Faction::Unaligned,
             axioms: AxiomEffects { axioms: vec![
                 (Form::MomentumBeam, Function::FlatStealSouls { dam: 10 }),
                 (Form::MomentumBeam, Function::FlatStealSouls { dam: 10 }),
                 (Form::MomentumBeam, Function::Empty),
                 (Form::MomentumBeam, Function::Empty),
-            ], polarity: vec![-1,-1,-1,-1], status: vec![
-                Effect{stacks: 1, effect_type: EffectType::Glamour},
-                Effect{stacks: 1, effect_type: EffectType::Discipline},
-                Effect{stacks: 1, effect_type: EffectType::Grace},
-                Effect{stacks: 1, effect_type: EffectType::Pride},
-            ]},
+            ], polarity: vec![-1,-1,-1,-1], status: Vec::new()},
+            virtues: Virtues::default(),
             thought: Thought {stored_path: None},
+            initiative: Initiative {energy: ACTION_COST/2, ready: false}, // Halfway there, so that nobody has to wait out the very first tick.
+            reactions: ReactiveAxioms::default(),
         }
     }
     pub fn with_data(
         mut self,
         x: usize,
         y: usize,
-        offset: (f32, f32),
-        start_anim: Option<(f32, f32)>,
         species: Species,
     ) -> Self{
         self = self.with_species(species);
-        self = self.with_position(x, y, offset, start_anim);
+        self = self.with_position(x, y);
         self
     }
-    pub fn with_position(mut self, x: usize, y: usize, offset: (f32, f32), start_anim: Option<(f32, f32)>) -> Self {
+    pub fn with_position(mut self, x: usize, y: usize) -> Self {
         self.position.x = x;
         self.position.y = y;
-        let end = Vec3::new(self.position.x as f32/2. + offset.0, self.position.y as f32/2. + offset.1, self.sprite_bundle.transform.translation.z);
-        let start = match start_anim {
-            None => end,
-            Some((sx, sy)) => Vec3 { x: sx, y: sy, z: end.z}
-        };
-        let tween = Tween::new(
-            EaseFunction::QuadraticInOut,
-            Duration::from_millis(500),
-            TransformPositionLens {
-                start,
-                end
-            },
-        );
-        self.animation.set_tweenable(tween);
         self
     }
     pub fn with_species(mut self, species: Species) -> Self {
-        self.sprite_bundle.sprite.index = match_species_with_sprite(&species);
         self.name = Name::new(match_species_with_name(&species));
-        self.sprite_bundle.transform.rotation = match_species_with_rotation(&species);
-        if is_intangible(&species){
-            self.sprite_bundle.transform.translation.z = -0.1;
-        }
-        if is_invisible(&species){
-            self.sprite_bundle.visibility = Visibility::Hidden;
-        }
         (self.axioms.axioms, self.axioms.polarity) = match_species_with_axioms(&species);
+        let definition = match_species_with_definition(&species);
+        self.reactions.reactions = definition.reactions.clone();
+        (self.breath.hand_size, self.breath.draw) = (definition.hand_size, definition.draw);
         self.faction = match_species_with_faction(&species);
         self.species = species;
         self
     }
 }
 
-#[derive(Component, PartialEq, Eq, Hash, Clone, Debug)]
+#[derive(Bundle)]
+pub struct CreatureSpriteBundle { // The on-screen mirror of a creature, attached once it has been placed down.
+    sprite_bundle: SpriteSheetBundle,
+    animation: Animator<Transform>,
+}
+
+impl CreatureSpriteBundle {
+    pub fn new(
+        tex_handle: &SpriteSheetHandle,
+        species: &Species,
+        start: Vec3,
+        end: Vec3,
+    ) -> Self {
+        let z = if is_intangible(species) { -0.1 } else { 0. };
+        let tween = Tween::new(
+            EaseFunction::QuadraticInOut,
+            Duration::from_millis(500),
+            TransformPositionLens {
+                start: Vec3 { z, ..start },
+                end: Vec3 { z, ..end },
+            },
+        );
+        let visibility = if is_invisible(species) { Visibility::Hidden } else { Visibility::Visible };
+        Self{
+            sprite_bundle : SpriteSheetBundle {
+                texture_atlas: tex_handle.handle.clone(),
+                sprite: TextureAtlasSprite{
+                    index : match_species_with_sprite(species),
+                    custom_size: Some(Vec2::new(1., 1.)),
+                    ..default()
+                },
+                transform: Transform {
+                    translation: Vec3 { z, ..start },
+                    rotation: match_species_with_rotation(species),
+                    scale: Vec3{x: 0.5, y: 0.5, z:1.},
+                },
+                visibility,
+                ..default()
+            },
+            animation: Animator::new(tween),
+        }
+    }
+}
+
+#[allow(clippy::enum_variant_names)] // BuggedSpecies is its key in species.ron and in saves.
+#[derive(Component, PartialEq, Eq, Hash, Clone, Debug, Serialize, Deserialize)]
 pub enum Species {
     Wall,
     Terminal,
@@ -154,94 +151,183 @@ pub enum Species {
     PlantSegment,
 }
 
+/*
+
+What each Species looks like and how it behaves is read from assets/species.ron the first time it is needed,
+keyed by the name of its variant. The functions below only add what depends on the data a Species carries,
+like an Airlock's direction or the Function stored in a crate.
+
+*/
+
+pub const SPECIES_PATH: &str = "assets/species.ron";
+const SPECIES_RON: &str = include_str!("../assets/species.ron"); // Baked in, so the game runs from any directory.
+
+static SPECIES_REGISTRY: OnceLock<HashMap<String, SpeciesDefinition>> = OnceLock::new();
+
+#[derive(Deserialize)]
+pub struct SpeciesDefinition {
+    pub name: String,
+    pub sprite: usize,
+    #[serde(default)]
+    pub faction: Faction,
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default = "default_speed")]
+    pub speed: i32,
+    #[serde(default = "default_hand_size")]
+    pub hand_size: usize,
+    #[serde(default)]
+    pub draw: DrawRule,
+    #[serde(default, rename = "axioms")]
+    pub axiom_source: String, // Written in the Axiom language of dsl.rs.
+    #[serde(skip)]
+    pub axioms: Vec<(Form, Function)>,
+    #[serde(skip)]
+    pub polarity: Vec<i32>,
+    #[serde(default, rename = "reactions")]
+    pub reaction_source: HashMap<TriggerType, String>, // One Axiom each, in the same language.
+    #[serde(skip)]
+    pub reactions: HashMap<TriggerType, (Form, Function)>,
+    #[serde(default)]
+    pub flags: Vec<SpeciesFlag>,
+    #[serde(default)]
+    pub colour: MapColour,
+    #[serde(default)]
+    pub description: usize,
+}
+
+#[derive(Deserialize, PartialEq, Eq, Debug)]
+pub enum SpeciesFlag {
+    Intangible, // Pass-through, off the grid.
+    GrabPoint, // The Terminal can hold on to it instead of falling.
+    Invisible,
+    Openable,
+    Soulless, // Gets no Souls when they are handed out.
+    Pushable,
+}
+
+fn default_speed() -> i32 { 12 }
+
+fn default_hand_size() -> usize { DEFAULT_HAND_SIZE }
+
+// Read from the assets folder when there is one, so species can be edited without recompiling.
+pub fn species_registry() -> &'static HashMap<String, SpeciesDefinition> {
+    SPECIES_REGISTRY.get_or_init(|| {
+        let edited = match fs::read_to_string(SPECIES_PATH) {
+            Ok(text) => parse_species(&text),
+            Err(err) => Err(format!("Could not read {SPECIES_PATH}: {err}")),
+        };
+        edited.unwrap_or_else(|err| {
+            warn!("{err} Using the species built into the game instead.");
+            parse_species(SPECIES_RON).unwrap_or_else(|err| panic!("The species built into the game are malformed! {err}"))
+        })
+    })
+}
+
+pub fn parse_species(
+    text: &str,
+) -> Result<HashMap<String, SpeciesDefinition>, String> {
+    let mut registry: HashMap<String, SpeciesDefinition> = ron::from_str(text).map_err(|err| format!("Could not parse {SPECIES_PATH} at {err}."))?;
+    for (key, definition) in registry.iter_mut() {
+        let (mut axioms, mut polarity) = parse_axioms(&definition.axiom_source).map_err(|err| format!("The Axioms of {key} in {SPECIES_PATH} are malformed, {err}."))?;
+        if axioms.len() > 4 {
+            return Err(format!("{key} in {SPECIES_PATH} has {} Axioms, but there are only 4 Soul castes to cast them with.", axioms.len()));
+        }
+        axioms.resize(4, (Form::Empty, Function::Empty));
+        if definition.hand_size > MAX_HAND_SIZE {
+            return Err(format!("{key} in {SPECIES_PATH} holds {} Souls, but only {MAX_HAND_SIZE} can be cast with the number keys.", definition.hand_size));
+        }
+        polarity.resize(4, 0);
+        (definition.axioms, definition.polarity) = (axioms, polarity);
+        for (trig, source) in definition.reaction_source.iter() {
+            let (reaction, _) = parse_axioms(source).map_err(|err| format!("The {trig:?} reaction of {key} in {SPECIES_PATH} is malformed, {err}."))?;
+            let [reaction] = <[_; 1]>::try_from(reaction).map_err(|_| format!("The {trig:?} reaction of {key} in {SPECIES_PATH} must be exactly one Axiom."))?;
+            definition.reactions.insert(trig.clone(), reaction);
+        }
+    }
+    Ok(registry)
+}
+
+// The name of the variant, without the data it carries: "EpsilonHead { len: 3 }" becomes "EpsilonHead".
+fn species_key(
+    species: &Species
+) -> String {
+    let debug = format!("{species:?}");
+    debug.split([' ', '{', '(']).next().unwrap_or_default().to_owned()
+}
+
+pub fn match_species_with_definition(
+    species: &Species
+) -> &'static SpeciesDefinition {
+    let registry = species_registry();
+    let key = species_key(species);
+    match registry.get(&key) {
+        Some(definition) => definition,
+        None => {
+            warn!("{key} is missing from {SPECIES_PATH}!");
+            registry.get("BuggedSpecies").unwrap_or_else(|| panic!("BuggedSpecies is missing from {SPECIES_PATH}!"))
+        }
+    }
+}
+
+fn has_flag(
+    species: &Species,
+    flag: SpeciesFlag,
+) -> bool {
+    match_species_with_definition(species).flags.contains(&flag)
+}
+
 pub fn match_species_with_sprite(
     species: &Species
 )-> usize{
+    let sprite = match_species_with_definition(species).sprite;
     match species{
-        Species::Wall => 3,
-        Species::BuggedSpecies => 1,
-        Species::Terminal => 0,
-        Species::Void => 2,
-        Species::Felidol => 49,
-        Species::TermiWall => 37,
-        Species::RiftBorder { dir: _ } => 108,
-        Species::Projector => 2,
-        Species::EpsilonHead {len: _} => 67,
-        Species::EpsilonTail {order: _} => 68,
-        Species::LunaMoth => 44,
-        Species::AxiomCrate => 19,
-        Species::Harmonizer => 26,
-        Species::GlamourCrate => 19,
-        Species::DisciplineCrate => 20,
-        Species::GraceCrate => 21,
-        Species::PrideCrate => 22,
-        Species::Airlock {dir: _ } => 17,
-        Species::ChromeNurse => 6,
-        Species::SegmentTransformer => 78,
-        Species::CrateActivator { caste } => 160+caste,
-        Species::FormCrate { form: _ } => 20,
-        Species::FunctionCrate { function: _ } => 21,
-        Species::Platform => 57,
-        Species::Ladder => 58,
-        Species::PlantSegment => 43,
+        Species::CrateActivator { caste } => sprite+caste,
+        _ => sprite,
     }
 }
 
 pub fn match_species_with_faction(
     species: &Species
 ) -> Faction {
-    match species {
-        Species::LunaMoth => Faction::Feral,
-        Species::EpsilonHead { len: _ }=> Faction::Ordered,
-        Species::EpsilonTail {order: _}=> Faction::Ordered,
-        Species::Terminal => Faction::Ordered,
-        Species::SegmentTransformer => Faction::Ordered,
-        _ => Faction::Unaligned,
-    }
+    match_species_with_definition(species).faction.clone()
 }
 
 pub fn match_species_with_name(
     species: &Species
 )-> String {
-    let ret = match species{
-        Species::Wall => "Rampart of Nacre",
-        Species::BuggedSpecies => "Bugged, Please Report",
-        Species::Terminal => "Terminal, the Reality Anchor",
-        Species::Felidol => "Greedswept Felidol",
-        Species::Void => "Bugged, Please Report",
-        Species::TermiWall => "Tangled Circuits",
-        Species::RiftBorder { dir: _ } => "Thought-Matter Rift",
-        Species::Projector => "Hypnotic Well",
-        Species::EpsilonHead{ len: _ } => "Epsilon, Adorned in Jade",
-        Species::EpsilonTail {order: _}=> "Rubberized Mecha-Segment",
-        Species::LunaMoth => "Cosmos Worn as Robes",
-        Species::AxiomCrate => "Axiomatic Crate",
-        Species::GlamourCrate => "Adorned Crate",
-        Species::DisciplineCrate => "Steel-Plated Crate",
-        Species::GraceCrate => "Vibrating Crate",
-        Species::PrideCrate => "Tar-Soaked Crate",
-        Species::Harmonizer => "Harmonic Organizer",
-        Species::Airlock {dir: _ } => "Quicksilver Curtains",
-        Species::ChromeNurse => "Chrome Attendant",
-        Species::SegmentTransformer => "Bio-Mechanizer",
-        Species::CrateActivator { caste: _ } => "Axiom Activator",
-        Species::FormCrate { form: _ } => "Form Crate",
-        Species::FunctionCrate { function: _ } => "Function Crate",
-        Species::Platform => "Pneumatic Platform",
-        Species::Ladder => "Ascendant Gust",
-        Species::PlantSegment => "World Stem",
-    }.to_owned();
-    ret
+    match_species_with_definition(species).name.clone()
 }
 
 pub fn match_species_with_priority(
     species: &Species
 ) -> i32 {
     match species{
-        Species::EpsilonHead { len: _ } => -2,
-        Species::Airlock { dir: _ } => -99,
         Species::EpsilonTail { order } => *order,
-        _ => 0,
+        _ => match_species_with_definition(species).priority,
+    }
+}
+
+// Energy a creature needs to bank before it can act. Speeds are measured against it:
+// at 12 a creature acts every other tick, which is every turn for a Terminal of average speed.
+pub const ACTION_COST: i32 = 24;
+
+pub fn match_species_with_speed(
+    species: &Species
+) -> i32 {
+    match_species_with_definition(species).speed
+}
+
+// Grace hurries a creature along, Discipline weighs it down.
+pub fn calculate_speed(
+    species: &Species,
+    virtues: &Virtues,
+) -> i32 {
+    match species{
+        Species::EpsilonTail { order: _ } => match_species_with_speed(species), // Segments only move when the head drags them along, so they must always be ready for it.
+        _ => {
+            (match_species_with_speed(species) + virtues.grace as i32 - virtues.discipline as i32).clamp(1, ACTION_COST)
+        }
     }
 }
 
@@ -249,36 +335,6 @@ pub fn match_species_with_axioms(
     species: &Species
 ) -> (Vec<(Form, Function)>,Vec<i32>) {
     match species{
-        Species::LunaMoth => (vec![
-            (Form::Ego, Function::MomentumDash),
-            (Form::MomentumTouch, Function::StealSouls),
-            (Form::MomentumBeam, Function::MomentumReverseDash),
-            (Form::MomentumBeam, Function::MomentumReverseDash), // Circlet slash, pull closer?
-        ], vec![1,-2,-1,-1] ),
-        Species::EpsilonHead { len: _ } => (vec![
-            (Form::MomentumBeam, Function::MomentumReverseDash),
-            (Form::MomentumLateral, Function::Coil),
-            (Form::MomentumBeam, Function::StealSouls),
-            (Form::SmallBurst, Function::BlinkOuter),
-        ], vec![-1,-1,-1,0] ),
-        Species::Terminal => (vec![
-            (Form::MomentumBeam, Function::SummonCreature { species: Species::ChromeNurse }),
-            (Form::MomentumBeam, Function::MomentumReverseDash),
-            (Form::SmallBurst, Function::Synchronize),
-            (Form::SmallBurst, Function::CyanCharm), // TODO there is an infinite loop, fix it
-        ], vec![0,0,0,0]),
-        Species::ChromeNurse => (vec![
-            (Form::MomentumBeam, Function::MarkPatient),
-            (Form::MomentumBeam, Function::MarkPatient),
-            (Form::MomentumBeam, Function::MarkPatient),
-            (Form::Empty, Function::Empty),
-        ], vec![1,1,1,0]),
-        Species::SegmentTransformer => (vec![
-            (Form::MomentumTouch, Function::Segmentize),
-            (Form::MomentumTouch, Function::Segmentize),
-            (Form::MomentumTouch, Function::Segmentize),
-            (Form::Empty, Function::Empty),
-        ], vec![-1,-1,-1,0]),
         Species::FormCrate { form } => (vec![
             (form.clone(), Function::Empty),
             (form.clone(), Function::Empty),
@@ -291,28 +347,23 @@ pub fn match_species_with_axioms(
             (Form::Empty, *function.clone()),
             (Form::Empty, *function.clone()),
         ], vec![0,0,0,0] ),
-        _ => (vec![
-            (Form::Empty, Function::Empty),
-            (Form::Empty, Function::Empty),
-            (Form::Empty, Function::Empty),
-            (Form::Empty, Function::Empty),
-        ], vec![0,0,0,0] ),
+        _ => {
+            let definition = match_species_with_definition(species);
+            (definition.axioms.clone(), definition.polarity.clone())
+        }
     }
 }
 
-pub fn match_species_with_pixel(
+pub fn match_species_with_description(
     species: &Species
 ) -> usize {
-    match_color_with_pixel(&match_species_with_color(species))
+    match_species_with_definition(species).description
 }
 
-fn match_species_with_color(
+pub fn match_species_with_pixel(
     species: &Species
-) -> MapColour {
-    match species{
-        Species::Terminal => MapColour::Plum,
-        _ => MapColour::White
-    }
+) -> usize {
+    match_color_with_pixel(&match_species_with_definition(species).colour)
 }
 
 fn match_color_with_pixel(
@@ -337,68 +388,38 @@ pub fn match_species_with_rotation(
 pub fn is_intangible(
     species: &Species
 ) -> bool{
-    match species{
-        Species::RiftBorder { dir: _ } => true,
-        Species::Projector => true,
-        //Species::Platform => true,
-        Species::Ladder => true,
-        _ => false
-    }
+    has_flag(species, SpeciesFlag::Intangible)
 }
 
+#[allow(dead_code)] // Nothing climbs yet, but the flag is already in species.ron.
 pub fn is_grab_point(
     species: &Species
 ) -> bool{
-    match species{
-        Species::Platform => true,
-        Species::Ladder => true,
-        _ => false
-    }
+    has_flag(species, SpeciesFlag::GrabPoint)
 }
 
 pub fn is_invisible(
     species: &Species
 ) -> bool {
-    match species {
-        Species::Void => true,
-        Species::Projector => true,
-        _ => false,
-    }
+    has_flag(species, SpeciesFlag::Invisible)
 }
 
 pub fn is_openable(
     species: &Species
 ) -> bool {
-    match species {
-        Species::Airlock { dir: _ } => true,
-        _ => false,
-    }
+    has_flag(species, SpeciesFlag::Openable)
 }
 
 pub fn is_soulless(
     species: &Species
 ) -> bool {
-    match species {
-        Species::Terminal => false,
-        Species::EpsilonHead { len: _ } => false,
-        Species::ChromeNurse => false,
-        Species::SegmentTransformer => false,
-        _ => true,
-    }
+    has_flag(species, SpeciesFlag::Soulless)
 }
 
 pub fn is_pushable(
     species: &Species
 ) -> bool {
-    match species {
-        Species::GlamourCrate => true,
-        Species::DisciplineCrate => true,
-        Species::GraceCrate => true,
-        Species::PrideCrate => true,
-        Species::FormCrate { form: _ } => true,
-        Species::FunctionCrate { function: _ } => true,
-        _ => false,
-    }
+    has_flag(species, SpeciesFlag::Pushable)
 }
 
 pub fn match_faction_with_index(
@@ -412,4 +433,23 @@ pub fn match_faction_with_index(
         Faction::Serene => Some(4),
         Faction::Unaligned => None,
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_built_in_species_parse() {
+        let registry = parse_species(SPECIES_RON).unwrap();
+        assert!(registry.contains_key("BuggedSpecies"));
+    }
+
+    #[test]
+    fn malformed_species_are_an_error() {
+        let Err(err) = parse_species("{ \"Terminal\": (name: \"Terminal\", sprite: ") else { panic!("Half a species parsed!") };
+        assert!(err.starts_with("Could not parse"), "{err}");
+        let Err(err) = parse_species("{ \"Terminal\": (name: \"Terminal\", sprite: 0, hand_size: 99) }") else { panic!("99 Souls fit in a hand!") };
+        assert!(err.contains("holds 99 Souls"), "{err}");
+    }
+}
@@ -8,20 +8,9 @@ pub const LORE: &[&str] = &[
 "Focused Thought Pierces the Veil - Form\nThe Caster shoots a linear beam in the direction of its Momentum, stopping at the first Creature hit. All Tiles touched, including the contacted Creature, are Targeted.",
 ];
 
-pub fn match_species_with_description(
-    species: &Species
-) -> usize {
-    match species {
-        Species::EpsilonHead { len: _ } => 0,
-        _ => 0,
-    }
-}
-
 use bevy::{text::TextStyle, asset::AssetServer, ecs::system::Res, render::color::Color, log::info};
 use regex::Regex;
 
-use crate::species::Species;
-
 pub fn split_text(
     text: &str,
     asset_server: &Res<AssetServer>,
@@ -1,39 +1,67 @@
-use std::{collections::HashMap, f32::consts::PI, mem::swap, time::Duration};
+use std::{f32::consts::PI, fs::OpenOptions, io::Write, mem::{self, discriminant}, time::Duration};
 
-use bevy::prelude::*;
+use bevy::{ecs::schedule::SystemConfigs, prelude::*, utils::HashMap}; // Bevy's HashMap iterates in the same order every run, unlike std's.
 use bevy_tweening::{*, lens::{TransformPositionLens, TransformScaleLens, TransformRotationLens}};
-use rand::{seq::SliceRandom, thread_rng};
+use rand::seq::SliceRandom;
 
-use crate::{ai::has_effect, axiom::{grab_coords_from_form, match_axiom_with_soul, match_effect_with_decay, match_effect_with_gain, match_effect_with_minimum, match_soul_with_axiom, reduce_down_to, tup_i32_to_usize, tup_usize_to_i32, CasterInfo, Effect, EffectType, Form, Function, PlantAxiom, TriggerType}, components::{AxiomEffects, DoorAnimation, EffectMarker, Faction, Plant, Position, QueuedAction, RealityAnchor, Segmentified, SoulBreath, Thought, Wounded}, input::ActionType, map::{bresenham_line, get_all_factions_except_one, get_astar_best_move, get_best_move, get_empty_neighbours, get_neighbouring_entities, get_neighbours, is_in_bounds, manhattan_distance, pathfind_to_location, xy_idx, WorldMap, WORLD_HEIGHT, WORLD_WIDTH}, soul::{get_soul_rot_position, match_soul_with_display_index, match_soul_with_sprite, select_random_entities, CurrentEntityInUI, Soul, SoulRotationTimer}, species::{is_grab_point, is_intangible, is_openable, is_pushable, match_faction_with_index, match_species_with_priority, match_species_with_sprite, CreatureBundle, Species}, ui::{CenterOfWheel, LogMessage}, vaults::{get_build_sequence, Vault}, SoulSlot, SpriteSheetHandle, TurnState, ZoomInEffect};
+use crate::{ai::has_effect, rng::GameRng, axiom::{grab_coords_from_form, take_census, AxiomError, AxiomEvent, AxiomLog, AxiomOutcome, AxiomTarget, Resolution, AXIOM_LOG_LENGTH, match_axiom_with_soul, match_effect_with_definition, match_soul_with_axiom, fill_trigger_amount, reduce_down_to, remember_crescent, scale_function, tup_i32_to_usize, tup_usize_to_i32, CasterInfo, Effect, EffectType, Form, Function, PlantAxiom, Stacking, TriggerType, Census}, components::{AxiomEffects, Virtues, DoorAnimation, DrawRule, EffectMarker, Faction, Initiative, Plant, Position, QueuedAction, ReactiveAxioms, RealityAnchor, Segmentified, SoulBreath, Summoned, Thought, Wounded}, harmony::{tally_harmony, Harmony}, input::ActionType, map::{bresenham_line, get_all_factions_except_one, get_astar_best_move, get_empty_neighbours, get_neighbouring_entities, get_neighbours, is_in_bounds, manhattan_distance, pathfind_to_location, place_down_new_entities, xy_idx, WorldMap, WORLD_HEIGHT, WORLD_WIDTH}, replay::record_action, rewind::snapshot_turn, soul::{match_soul_with_display_index, select_random_souls, spawn_flying_soul, CurrentEntityInUI, Soul}, species::{calculate_speed, is_intangible, is_openable, is_pushable, is_soulless, match_species_with_name, match_faction_with_index, ACTION_COST, match_species_with_sprite, CreatureBundle, Species}, ui::{LogMessage, LogNotice}, vaults::{get_build_sequence, Vault}, SoulSlot, SpriteSheetHandle, TurnState, ZoomInEffect};
 
 pub struct TurnPlugin;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum Animation{
     Passage,
-    SoulDrain {source: Vec3, destination: Vec3, drained: Vec<Entity>},
+    SoulDrain {source: (usize, usize), destination: (usize, usize), drained: Vec<Soul>},
     FormMark {coords: (usize, usize)},
     Soulless,
     MessagePrint,
     SoulSwap,
     Polymorph {new_species: Species},
+    #[allow(dead_code)] // Only pushed by code that is commented out for now.
     RevealCreature,
     UseDoor {orient: usize, closing: bool},
     RemoveDoorAnims {closed: bool},
+    #[allow(dead_code)]
     MinimumDelay,
 }
 
 impl Plugin for TurnPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, calculate_actions.run_if(in_state(TurnState::CalculatingResponse)));
-        app.add_systems(Update, execute_turn.run_if(in_state(TurnState::ExecutingTurn)));
-        app.add_systems(Update, dispense_functions.run_if(in_state(TurnState::DispensingFunctions)));
+        app.add_systems(Update, turn_systems().run_if(in_state(TurnState::CalculatingResponse)));
         app.add_systems(Update, unpack_animations.run_if(in_state(TurnState::UnpackingAnimation)));
         app.add_systems(Update, fade_effects);
+        app.add_event::<AxiomEvent>();
+        app.init_resource::<AxiomLog>();
+        app.insert_resource(Resolution::from_env());
         app.insert_resource(TurnCount{turns: 0});
     }
 }
 
+/*
+
+The rules of a single tick, in the order they happen. The game runs them all at once as soon as the player
+has picked an action, then plays back the animations they queued. A Simulation runs this very same chain
+with nobody watching, so a turn is settled the same way on screen and in the tests.
+
+*/
+
+pub fn turn_systems() -> SystemConfigs {
+    (
+        snapshot_turn, // Before anything moves, so the turn can be taken back or played again.
+        record_action,
+        place_down_new_entities, // Creatures spawned since last turn, including summoned ones.
+        calculate_actions,
+        apply_deferred,
+        execute_turn,
+        apply_deferred,
+        dispense_functions,
+        apply_deferred,
+        log_axiom_events,
+        tally_harmony,
+    ).chain()
+}
+
+// How many times the player has acted. Ticks spent catching up on initiative don't count.
 #[derive(Resource)]
 pub struct TurnCount {
     pub turns: usize,
@@ -46,16 +74,13 @@ fn connect_soul_chain (
     let mut process = vec![start];
     let mut output = vec![world_map[xy_idx(start.0, start.1)].unwrap()];
 
-    while !process.is_empty() {
-        let (x, y) = process.pop().unwrap();
+    while let Some((x, y)) = process.pop() {
         let result = get_neighbouring_entities(world_map, x, y);
         let coords = get_neighbours(x, y);
-        for i in 0..result.len() {
-            if result[i].is_some() {
-                if !output.contains(&result[i].unwrap()) {
-                    process.push(coords[i].unwrap());
-                    output.push(result[i].unwrap());
-                }
+        for (found, coords) in result.into_iter().zip(coords) {
+            if let Some(found) = found.filter(|found| !output.contains(found)) {
+                process.push(coords.unwrap());
+                output.push(found);
             }
         }
     }
@@ -71,6 +96,7 @@ fn choose_action (
     available_souls: Vec<&Soul>,
     info: CasterInfo,
     world_map: &[Option<Entity>],
+    census: &Census,
 ) -> ActionType {
     let mut scores = [0,0,0,0];
     for (i, (form, _function)) in axioms.iter().enumerate() {
@@ -78,18 +104,13 @@ fn choose_action (
             scores[i] = -99;
             continue;
         }
-        let results = grab_coords_from_form(world_map, form.clone(), info.clone()); // It is checking all its combos even though not all of them might be available. Potential optimization?
+        let results = grab_coords_from_form(world_map, form.clone(), info.clone(), census); // It is checking all its combos even though not all of them might be available. Potential optimization?
         for target in results.entities {
             if foes.contains(&target) {scores[i] -= polarity[i]} else if allies.contains(&target) { scores[i] += polarity[i] };
         }
     }
-    match info.species {
-        Species::EpsilonHead { len: _ } => {
-            if has_effect(&info.effects, EffectType::Meltdown).is_some() {
-                scores[3] = 99;
-            }
-        }
-        _ => ()
+    if matches!(info.species, Species::EpsilonHead { .. }) && has_effect(&info.effects, EffectType::Meltdown).is_some() {
+        scores[3] = 99;
     }
     let (score_index, score) = scores.iter().enumerate().max_by_key(|&(_, x)| x).unwrap();
     let desired_soul = match_axiom_with_soul(score_index);
@@ -100,38 +121,46 @@ fn choose_action (
     if let Some(momentum) = momentum {  ActionType::Walk { momentum } } else { ActionType::Nothing }
 }
 
-fn calculate_actions (
-    mut creatures: Query<(Entity, &mut QueuedAction, &AxiomEffects, &SoulBreath, &Position, &Species, &Faction, Has<RealityAnchor>)>,
+pub fn calculate_actions (
+    mut creatures: Query<(Entity, &mut QueuedAction, &AxiomEffects, &Virtues, &SoulBreath, &Position, &Species, &Faction, &mut Initiative, Has<RealityAnchor>)>,
     read_species: Query<&Species>,
     read_position: Query<&Position>,
     read_thought: Query<&Thought>,
     locate_wounded: Query<(Entity, &Position), With<Wounded>>,
     locate_segments: Query<Entity, With<Segmentified>>,
-    souls: Query<&Soul>,
-    mut next_state: ResMut<NextState<TurnState>>,
     mut world_map: ResMut<WorldMap>,
     mut commands: Commands,
     mut turn_count: ResMut<TurnCount>,
 ){
-    turn_count.turns += 1;
     let mut contestants = Vec::new();
     for _i in 0..5 {
         contestants.push(Vec::new());
     }
-    for (entity, _queue, _ax, brea, _pos, _species, faction, _is_player) in creatures.iter_mut(){
+    let census = take_census(creatures.iter().map(|(entity, _, _, _, _, _, species, faction, _, _)| (entity, species, faction)));
+    for (entity, _queue, _ax, _virtues, brea, _pos, _species, faction, _initiative, _is_player) in creatures.iter_mut(){
         let index = match_faction_with_index(faction);
-        if index.is_some() && !brea.soulless { contestants[index.unwrap()].push(entity); } else { continue;} // Gather the pool of fighters by faction.
+        if let Some(index) = index.filter(|_| !brea.soulless) { contestants[index].push(entity); } else { continue;} // Gather the pool of fighters by faction.
     }
-    for (entity, mut queue, ax, brea, pos, species, faction, is_player) in creatures.iter_mut(){
+    for (entity, mut queue, ax, virtues, brea, pos, species, faction, mut initiative, is_player) in creatures.iter_mut(){
+        initiative.energy += calculate_speed(species, virtues);
+        initiative.ready = initiative.energy >= ACTION_COST;
+        if initiative.ready {
+            initiative.energy -= ACTION_COST;
+            if is_player {
+                turn_count.turns += 1;
+            }
+        } else {
+            queue.action = ActionType::Nothing; // Still catching up.
+            continue;
+        }
         if brea.soulless && !matches!(species, &Species::EpsilonTail { .. }){ // this caused a weird bug with the segments
             queue.action = ActionType::Nothing;
         }
         let mut foes = Vec::new();
         let mut allies = Vec::new();
-        let fac_index = match_faction_with_index(faction);
-        if fac_index.is_some() {
-            allies = contestants[fac_index.unwrap()].clone(); // Gather foes and allies of this creature's faction.
-            foes = get_all_factions_except_one(&mut contestants.clone(), fac_index.unwrap());
+        if let Some(fac_index) = match_faction_with_index(faction) {
+            allies = contestants[fac_index].clone(); // Gather foes and allies of this creature's faction.
+            foes = get_all_factions_except_one(&mut contestants.clone(), fac_index);
         }
         let destination = {
             let target = if foes.is_empty() { None } else {Some(foes[0])}; // Get the first foe available as a walking destination.
@@ -142,24 +171,17 @@ fn calculate_actions (
                 }
                 let mut tail_detect = None;
                 for tail in located_seg {
-                    if matches!(read_species.get(tail).unwrap(), Species::EpsilonTail { order: -1 }) {tail_detect = Some(tail); break;} else {continue;}
+                    if matches!(read_species.get(tail), Ok(Species::EpsilonTail { order: -1 })) {tail_detect = Some(tail); break;} else {continue;}
                 }
                 tail_detect
             } else {target};
-            if target.is_none() {
-                (22, 22)
-            } else {
-                let tar_pos = read_position.get(target.unwrap());
-                let tar_pos = tar_pos.unwrap();
-                (tar_pos.x, tar_pos.y)
+            match target.and_then(|target| read_position.get(target).ok()) {
+                Some(tar_pos) => (tar_pos.x, tar_pos.y),
+                None => (22, 22),
             }
         };
-        let (glamour, discipline, grace, pride) = (ax.status[0].stacks, ax.status[1].stacks,ax.status[2].stacks,ax.status[3].stacks);
-        let info = CasterInfo{entity, pos: (pos.x, pos.y), species: species.clone(), momentum: pos.momentum, is_player, glamour, grace, discipline, pride, effects: ax.status.clone() };
-        let mut available_souls = Vec::with_capacity(4);
-        for av in &brea.held {
-            if let Ok(soul_type) = souls.get(*av) { available_souls.push(soul_type) };
-        }
+        let info = CasterInfo::new(entity, pos, species, virtues, &ax.status, is_player);
+        let available_souls: Vec<&Soul> = brea.held.iter().collect();
         let saved_play_action = if is_player { queue.action.clone() } else { ActionType::Nothing };
         queue.action = match species {
             Species::SegmentTransformer => {
@@ -172,9 +194,9 @@ fn calculate_actions (
                     let dist_b = manhattan_distance(tup_usize_to_i32((b.1.x, b.1.y)), tup_usize_to_i32(info.pos));
                     dist_a.cmp(&dist_b)
                 });
-                let grab_this = located_wounded.get(0);
+                let grab_this = located_wounded.first();
                 if let Some(grab_this) = grab_this {  
-                    choose_action(info.pos, vec![grab_this.0], Vec::new(), ax.axioms.clone(), ax.polarity.clone(), available_souls, info, &world_map.entities)
+                    choose_action(info.pos, vec![grab_this.0], Vec::new(), ax.axioms.clone(), ax.polarity.clone(), available_souls, info, &world_map.entities, &census)
                 } else {ActionType::Nothing}            }
             Species::ChromeNurse => {
                 let mut patient = None;
@@ -184,10 +206,9 @@ fn calculate_actions (
                         _ => continue
                     }
                 }
-                match patient {
-                    Some(found) => {
-                        let patient_pos = read_position.get(found).unwrap();
-                        let mut patient_next_dest = match &read_thought.get(found).unwrap().stored_path {
+                match patient.and_then(|found| read_position.get(found).ok().map(|patient_pos| (found, patient_pos))) { // The patient might be gone.
+                    Some((found, patient_pos)) => {
+                        let mut patient_next_dest = match read_thought.get(found).ok().and_then(|thought| thought.stored_path.as_ref()) {
                             Some(seq) => {
                                 seq.0.clone().pop()
                             }
@@ -247,15 +268,15 @@ fn calculate_actions (
                             let dist_b = manhattan_distance(tup_usize_to_i32((b.1.x, b.1.y)), tup_usize_to_i32(info.pos));
                             dist_a.cmp(&dist_b)
                         });
-                        let grab_this = located_wounded.get(0);
+                        let grab_this = located_wounded.first();
                         if let Some(grab_this) = grab_this {  
-                            choose_action((grab_this.1.x, grab_this.1.y), foes, vec![grab_this.0], ax.axioms.clone(), ax.polarity.clone(), available_souls, info, &world_map.entities)
+                            choose_action((grab_this.1.x, grab_this.1.y), foes, vec![grab_this.0], ax.axioms.clone(), ax.polarity.clone(), available_souls, info, &world_map.entities, &census)
                         } else {ActionType::Nothing}
                     }
                 }
             }
             Species::LunaMoth => {
-                choose_action(destination, foes, allies, ax.axioms.clone(), ax.polarity.clone(), available_souls, info, &world_map.entities)
+                choose_action(destination, foes, allies, ax.axioms.clone(), ax.polarity.clone(), available_souls, info, &world_map.entities, &census)
             }
             Species::EpsilonHead { len } => {
                 let mut current_order = 0;
@@ -276,9 +297,9 @@ fn calculate_actions (
                     for detected in neigh{
                         match detected {
                             Some(creature) => {
-                                match read_species.get(creature).unwrap() {
-                                    Species::EpsilonTail {order: _} => {
-                                        let crea_pos = read_position.get(creature).unwrap();
+                                match read_species.get(creature) {
+                                    Ok(Species::EpsilonTail {order: _}) => {
+                                        let Ok(crea_pos) = read_position.get(creature) else { continue };
                                         let momentum = (self_pos.0 as i32-crea_pos.x as i32, self_pos.1 as i32-crea_pos.y as i32);
 
                                         if (momentum == self_mom || current_order >= *len ) && !detected_tails.contains(&creature){
@@ -304,17 +325,36 @@ fn calculate_actions (
                     world_map.targeted_axioms.push((entity, Function::ApplyEffect { effect: Effect {stacks: 2, effect_type: EffectType::Meltdown}}, info.clone()));
                 }
                 commands.entity(entity).insert(Species::EpsilonHead { len: current_order });
-                choose_action(destination, foes, allies, ax.axioms.clone(), ax.polarity.clone(), available_souls, info, &world_map.entities)
+                choose_action(destination, foes, allies, ax.axioms.clone(), ax.polarity.clone(), available_souls, info, &world_map.entities, &census)
             },
             Species::EpsilonTail {order: _} => {
-                queue.action.clone()
+                ActionType::Nothing // Only ever moves when its head drags it along.
             },
             _ => ActionType::Nothing,
         };
         if is_player { queue.action = saved_play_action; }
     }
+}
 
-    next_state.set(TurnState::ExecutingTurn);
+// Who acts over the next few ticks, in order, given each creature's (entity, energy, speed).
+// Within a tick, creatures act in the order they were given.
+pub fn predict_turn_order(
+    mut actors: Vec<(Entity, i32, i32)>,
+    count: usize,
+) -> Vec<Entity> {
+    let mut order = Vec::with_capacity(count);
+    if actors.is_empty() { return order; }
+    while order.len() < count {
+        for (entity, energy, speed) in actors.iter_mut() {
+            *energy += *speed;
+            if *energy >= ACTION_COST {
+                *energy -= ACTION_COST;
+                order.push(*entity);
+            }
+        }
+    }
+    order.truncate(count);
+    order
 }
 
 fn process_sequences(seq_def: &HashMap<PlantAxiom, Vec<Soul>>, seq: &Vec<Vec<Soul>>) -> Vec<PlantAxiom> {
@@ -351,32 +391,37 @@ fn process_sequences(seq_def: &HashMap<PlantAxiom, Vec<Soul>>, seq: &Vec<Vec<Sou
     output
 }
 
-fn execute_turn (
-    mut creatures: Query<(Entity, &QueuedAction, &Species, &mut AxiomEffects, &mut SoulBreath, &mut Position, Has<RealityAnchor>)>,
+pub fn execute_turn (
+    mut creatures: Query<(Entity, &QueuedAction, &Species, &mut AxiomEffects, &Virtues, &mut SoulBreath, &mut Position, Has<RealityAnchor>)>,
     read_action: Query<&QueuedAction>,
     read_species: Query<&Species>,
-    read_soul_slot: Query<&SoulSlot>,
-    mut next_state: ResMut<NextState<TurnState>>,
+    _read_soul_slot: Query<&SoulSlot>,
     mut world_map: ResMut<WorldMap>,
-    souls: Query<(&mut Animator<Transform>, &Transform, &Soul), Without<Position>>,
     turn_count: Res<TurnCount>,
+    mut rng: ResMut<GameRng>,
+    read_initiative: Query<&Initiative>,
+    read_census: Query<(Entity, &Species, &Faction)>,
 
     plants: Query<&Plant>,
 ){
     if turn_count.turns%10 == 1 {
         //world_map.targeted_axioms.push((play_ent, Function::MessageLog { message_id: turn_count.turns/10 }, CasterInfo::placeholder()));
     }
-    let seq_def = HashMap::from([
+    let census = take_census(read_census.iter());
+    let seq_def = HashMap::from_iter([
         (PlantAxiom::RandomHighest, vec![Soul::Vile]),
         (PlantAxiom::Grow, vec![Soul::Saintly, Soul::Vile]),
         (PlantAxiom::TimePasses, vec![Soul::Ordered]),
     ]);
 
     for plant in plants.iter() {
+        if !plant.stem.first().is_some_and(|seg| read_initiative.get(*seg).is_ok_and(|init| init.ready)) {
+            continue; // The plant grows at the pace of its stem.
+        }
 
-        let chained_blocks = connect_soul_chain(&world_map.entities, (0,0)); // TODO set a beginning point.
+        let _chained_blocks = connect_soul_chain(&world_map.entities, (0,0)); // TODO set a beginning point.
 
-        // For each chained_blocks, derive that into a Vec of souls with read_soul_slot, to replace let seq.
+        // For each _chained_blocks, derive that into a Vec of souls with _read_soul_slot, to replace let seq.
 
         let seq: &Vec<Vec<Soul>> = &plant.sequences;
         let program = process_sequences(&seq_def, seq);
@@ -391,24 +436,18 @@ fn execute_turn (
                         PlantAxiom::RandomHighest => {
                             let mut blocks = Vec::new();
                             for segment in &plant.stem {
-                                let stem_block = if let Ok((entity, _queue, _species, _effects, _breath, pos, _is_player)) = creatures.get(*segment) {
-                                    (entity, (pos.x, pos.y))
-                                } else { panic!("A stem block is lacking components!")};
-                                blocks.push(stem_block);
+                                // A stem block that was destroyed this turn no longer counts.
+                                let Ok((entity, _queue, _species, _effects, _virtues, _breath, pos, _is_player)) = creatures.get(*segment) else { continue };
+                                blocks.push((entity, (pos.x, pos.y)));
                             }
             
-                            let max_second = blocks.iter().map(|&(_, (_, b))| b).max().unwrap();// Find the highest Y value 
+                            let max_second = blocks.iter().map(|&(_, (_, b))| b).max().unwrap_or(0);// Find the highest Y value 
                             let max_pairs: Vec<(Entity, (usize, usize))> = blocks
                                 .drain(..)
                                 .filter(|(_, (_, b))| *b == max_second)
                                 .collect(); // Get all coords with that highest one
                         
-                            if max_pairs.is_empty() {
-                                Vec::new()
-                            } else {
-                                let mut rng = thread_rng();
-                                vec![max_pairs.choose(&mut rng).cloned().unwrap()]
-                            }
+                            max_pairs.choose(&mut rng.combat).cloned().into_iter().collect()
             
                         }
                         _ => Vec::new(),
@@ -424,82 +463,68 @@ fn execute_turn (
 
         for (function, chosen_targets) in payload.iter() {
             let form = Form::Artificial { coords: chosen_targets.clone() };
-            let info = if let Ok((entity, queue, species, effects, breath, pos, is_player)) = creatures.get(chosen_targets[0].0) {
-                let (glamour, discipline, grace, pride) = (effects.status[0].stacks, effects.status[1].stacks,effects.status[2].stacks,effects.status[3].stacks);
-                let info = CasterInfo{ entity, pos: (pos.x,pos.y), species: species.clone(), momentum: pos.momentum, is_player, glamour, grace, discipline, pride, effects: effects.status.clone()};
-                info
-            } else { panic!("A stem block is lacking components!")};
+            // Nothing to grow from if no stem block was chosen.
+            let Some(&(caster, _)) = chosen_targets.first() else { continue };
+            let Ok((entity, _queue, species, effects, virtues, _breath, pos, is_player)) = creatures.get(caster) else { continue };
+            let info = CasterInfo::new(entity, pos, species, virtues, &effects.status, is_player);
     
-            let targets = grab_coords_from_form(&world_map.entities, form, info.clone());
-            for target in targets.entities.clone() {
-                let info = if let Ok((entity, queue, species, effects, breath, pos, is_player)) = creatures.get(target) {
-                    let (glamour, discipline, grace, pride) = (effects.status[0].stacks, effects.status[1].stacks,effects.status[2].stacks,effects.status[3].stacks);
-                    let info = CasterInfo{ entity, pos: (pos.x,pos.y), species: species.clone(), momentum: pos.momentum, is_player, glamour, grace, discipline, pride, effects: effects.status.clone()};
-                    info
-                } else { panic!("A stem block is lacking components!")};
-    
-                world_map.targeted_axioms.push((target, function.clone(), info.clone()));
+            let targets = grab_coords_from_form(&world_map.entities, form, info.clone(), &census);
+            for target in targets.entities {
+                let Ok((entity, _queue, species, effects, virtues, _breath, pos, is_player)) = creatures.get(target) else { continue };
+                let target_info = CasterInfo::new(entity, pos, species, virtues, &effects.status, is_player);
+                world_map.targeted_axioms.push((target, function.clone(), target_info));
             }
-            for i in 0..targets.coords.len(){
-                let info = if let Ok((entity, queue, species, effects, breath, pos, is_player)) = creatures.get(targets.entities[i]) {
-                    let (glamour, discipline, grace, pride) = (effects.status[0].stacks, effects.status[1].stacks,effects.status[2].stacks,effects.status[3].stacks);
-                    let info = CasterInfo{ entity, pos: (pos.x,pos.y), species: species.clone(), momentum: pos.momentum, is_player, glamour, grace, discipline, pride, effects: effects.status.clone()};
-                    info
-                } else { panic!("A stem block is lacking components!")};
-                world_map.anim_queue.push((targets.entities[i], Animation::FormMark { coords: targets.coords[i] }));
-                world_map.floor_axioms.push((targets.coords[i], function.clone(), info.clone()));
+            for square in targets.coords {
+                world_map.anim_queue.push((info.entity, Animation::FormMark { coords: square }));
+                world_map.floor_axioms.push((square, function.clone(), info.clone()));
             }
         }
 
 
     }
 
-    for (entity, queue, species, mut effects, breath, mut pos, is_player) in creatures.iter_mut(){
+    for (entity, queue, species, mut effects, virtues, breath, mut pos, is_player) in creatures.iter_mut(){
         if is_player {
             world_map.anim_reality_anchor = entity;
         }
         (pos.ox, pos.oy) = (pos.x, pos.y); // To reset for the form mark animations
+        if read_initiative.get(entity).is_ok_and(|init| !init.ready) { continue; } // Effects wear off on the creature's own turns.
         let mut chosen_action = queue.action.clone();
         if breath.soulless && !matches!(species, &Species::EpsilonTail { .. }) {chosen_action = ActionType::Nothing;}
-        let (glamour, discipline, grace, pride) = (effects.status[0].stacks, effects.status[1].stacks,effects.status[2].stacks,effects.status[3].stacks);
-        let info = CasterInfo{ entity, pos: (pos.x,pos.y), species: species.clone(), momentum: pos.momentum, is_player, glamour, grace, discipline, pride, effects: effects.status.clone()};
+        let info = CasterInfo::new(entity, &pos, species, virtues, &effects.status, is_player);
         for eff in effects.status.iter() {
-            match eff.effect_type {
-                EffectType::Sync { link } => {
-                    let overwrite = read_action.get(link).map(|e| (e.action.clone())).unwrap();
-                    chosen_action = overwrite.clone();
-                    break;
+            if let EffectType::Sync { link } = eff.effect_type {
+                match read_action.get(link) {
+                    Ok(linked) => chosen_action = linked.action.clone(),
+                    Err(_) => warn!("{entity:?} is in Sync with {link:?}, which is gone, so it does its own thing."),
                 }
-                _ => ()
+                break;
             }
         }
         let adj = get_neighbouring_entities(&world_map.entities, pos.x, pos.y);
         let mut supported = false;
-        for pot in adj {
-            if let Some(tile) = pot { 
-                let sp = read_species.get(tile).unwrap();
-                if !is_intangible(sp) {supported = true;}
-            }
+        for tile in adj.into_iter().flatten() {
+            if read_species.get(tile).is_ok_and(|sp| !is_intangible(sp)) {supported = true;}
         }
         match chosen_action{
             ActionType::SoulCast { slot } => {
-                let soul = match breath.held.get(slot).cloned(){ // Check that we aren't picking an empty slot.
+                let soul = match breath.held.get(slot).copied(){ // Check that we aren't picking an empty slot.
                     Some(soul) => soul,
                     None => continue
                 };
-                if let Ok((_anim, _transform, soul_id), ) = souls.get(soul) {
-                    let axioms = effects.axioms.clone();
-                    let (form, function) = axioms[match_soul_with_axiom(soul_id)].clone();
-                    let targets = grab_coords_from_form(&world_map.entities, form, info.clone());
-                    for target in targets.entities {
-                        world_map.targeted_axioms.push((target, function.clone(), info.clone()));
-                    }
-                    for square in targets.coords{
-                        world_map.anim_queue.push((entity, Animation::FormMark { coords: square }));
-                        world_map.floor_axioms.push((square, function.clone(), info.clone()));
-                    }
-                } else {
-                    panic!("The used Soul did not have a soul type!");
+                let axioms = effects.axioms.clone();
+                let (form, function) = axioms[match_soul_with_axiom(&soul)].clone();
+                let crescent = form == Form::CrescentArc;
+                let targets = grab_coords_from_form(&world_map.entities, form, info.clone(), &census);
+                if crescent {
+                    remember_crescent(&mut effects.status, targets.entities.len());
+                }
+                for target in targets.entities {
+                    world_map.targeted_axioms.push((target, function.clone(), info.clone()));
+                }
+                for square in targets.coords{
+                    world_map.anim_queue.push((entity, Animation::FormMark { coords: square }));
+                    world_map.floor_axioms.push((square, function.clone(), info.clone()));
                 }
 
                 world_map.targeted_axioms.push((entity, Function::DiscardSoul { soul, slot }, info.clone()));
@@ -507,7 +532,7 @@ fn execute_turn (
                 // CASTING
                 // ++Glamour
                 // --Grace
-                world_map.targeted_axioms.push((entity, Function::TriggerEffect { trig: TriggerType::CastSoul }, info.clone()));
+                world_map.targeted_axioms.push((entity, Function::TriggerEffect { trig: TriggerType::CastSoul, amount: 1 }, info.clone()));
             }
             ActionType::Walk { momentum } => {
                 if supported || species != &Species::Terminal{
@@ -530,393 +555,428 @@ fn execute_turn (
             world_map.targeted_axioms.push((entity, Function::Dash {dx: info.momentum.0, dy: info.momentum.1}, info.clone()));
             world_map.targeted_axioms.push((entity, Function::AlterMomentum {alter: (0,-1)}, info.clone()));
         }
-        if effects.status.len() > 4 {
-            for eff in effects.status.iter_mut() {
-                if match_effect_with_decay(&eff.effect_type) == TriggerType::EachTurn || match_effect_with_gain(&eff.effect_type) == TriggerType::EachTurn { // If at least one turn-decay effect, tick them
-                    world_map.targeted_axioms.push((entity, Function::TriggerEffect { trig: TriggerType::EachTurn }, info.clone()));
-                    break;
-                }
+        if breath.draw == DrawRule::OnTurn {
+            world_map.targeted_axioms.push((entity, Function::RefillHand, info.clone()));
+        }
+        for eff in effects.status.iter() {
+            let definition = match_effect_with_definition(&eff.effect_type);
+            if definition.decay == TriggerType::EachTurn || definition.gain == TriggerType::EachTurn { // If at least one turn-decay effect, tick them
+                world_map.targeted_axioms.push((entity, Function::TriggerEffect { trig: TriggerType::EachTurn, amount: 1 }, info.clone()));
+                break;
             }
         }
     }
-    next_state.set(TurnState::DispensingFunctions);
 }
 
 
 
-fn dispense_functions(
+pub fn dispense_functions(
     mut creatures: ParamSet<(
-        Query<(&Transform, &mut Species, &mut SoulBreath, &mut AxiomEffects, &mut Animator<Transform>, &mut Position, Has<RealityAnchor>)>,
+        Query<(&mut Species, &mut SoulBreath, &mut AxiomEffects, &mut Virtues, &mut Position, Has<RealityAnchor>)>,
         Query<&Position>,
-        Query<&Species>,
-        Query<&SoulBreath>,
-        Query<(&Position, &Transform), With<RealityAnchor>>,
+        Query<(Entity, &Species, &Faction)>,
     )>,
     mut plant: Query<&mut Plant>,
     faction: Query<&Faction>,
+    reactive: Query<&ReactiveAxioms>,
     check_wound: Query<Entity, With<Wounded>>,
     mut next_state: ResMut<NextState<TurnState>>,
     mut world_map: ResMut<WorldMap>,
-    mut souls: Query<(&mut Animator<Transform>, &Transform, &mut TextureAtlasSprite, &mut Soul), Without<Position>>,
-    ui_center: Res<CenterOfWheel>,
-    time: Res<SoulRotationTimer>,
     mut events: EventWriter<LogMessage>,
+    mut notices: EventWriter<LogNotice>,
+    mut axiom_events: EventWriter<AxiomEvent>,
     mut zoom: ResMut<ZoomInEffect>,
     mut commands: Commands,
     mut current_crea_display: ResMut<CurrentEntityInUI>,
-    texture_atlas_handle: Res<SpriteSheetHandle>,
-
+    mut rng: ResMut<GameRng>,
+    resolution: Res<Resolution>,
 ){
     let mut anti_infinite_loop = 0;
-    /*world_map.targeted_axioms.sort_by(|a, b| { // 
-        match (a.2.is_player, b.2.is_player) {
-            (true, true) | (false, false) => Ordering::Equal,
-            (true, false) => Ordering::Less,
-            (false, true) => Ordering::Greater,
-        }
-    }); // The player acts first */
-    world_map.floor_axioms.sort_unstable_by(|a, b| match_species_with_priority(&b.2.species).cmp(&match_species_with_priority(&a.2.species)));
-    world_map.targeted_axioms.sort_unstable_by(|a, b| match_species_with_priority(&b.2.species).cmp(&match_species_with_priority(&a.2.species)));
-    // Then we grant special priorities
+    let mut resolved = Vec::new(); // What got resolved so far, to point out the culprit if the chain never ends.
+    let census = take_census(creatures.p2().iter()); // As things stood before anything resolved.
+    world_map.floor_axioms.begin(*resolution);
+    world_map.targeted_axioms.begin(*resolution);
 
-    while !world_map.floor_axioms.is_empty() {
+    loop {
+        // Take from whichever queue holds the earliest phase. Summons win ties.
+        let floor_first = match (world_map.floor_axioms.next_phase(), world_map.targeted_axioms.next_phase()) {
+            (None, None) => break,
+            (Some(floor), Some(targeted)) => floor <= targeted,
+            (floor, _) => floor.is_some(),
+        };
         anti_infinite_loop += 1;
-        if anti_infinite_loop > 500 { panic!("Infinite loop detected in axiom queue!") }
-        let (coords, function, mut info) = world_map.floor_axioms.pop().unwrap();
-        match function {
-            Function::SummonCreature { species } => {
-                let mut player_pos = (0.,0.);
-                let mut player_trans = (0., 0.);
-                if let Ok((pos, tra)) = creatures.p4().get_single() {
-                    player_pos = ((22. - pos.x as f32)/2., (8. - pos.y as f32)/2.);
-                    player_trans = (tra.translation.x, tra.translation.y);
-                }
-                if world_map.entities[xy_idx(coords.0, coords.1)].is_some() {continue;}
-                let new_creature = CreatureBundle::new(&texture_atlas_handle)
-                .with_data(coords.0, coords.1, player_pos, Some(player_trans), species.clone());
-                let entity_id = commands.spawn(new_creature).id();
-                let mut plant_ex = plant.get_single_mut().unwrap();
-                if species == Species::PlantSegment {
-                    plant_ex.stem.push(entity_id);
+        if anti_infinite_loop > AXIOM_BUDGET { break; }
+        if floor_first {
+            let (coords, function, info) = world_map.floor_axioms.pop().unwrap();
+            resolved.push(describe_function(&function));
+            let result = match function.clone() {
+                Function::SummonCreature { species } => 'summon: {
+                    if world_map.entities[xy_idx(coords.0, coords.1)].is_some() {break 'summon Ok(AxiomOutcome::Blocked);}
+                    let new_creature = CreatureBundle::new()
+                    .with_data(coords.0, coords.1, species.clone());
+                    let entity_id = commands.spawn((new_creature, Summoned)).id();
+                    if species == Species::PlantSegment {
+                        if let Ok(mut plant_ex) = plant.get_single_mut() {
+                            plant_ex.stem.push(entity_id);
+                        }
+                    }
+                    //commands.entity(entity_id).insert(Visibility::Hidden);
+                    //world_map.anim_queue.push((entity_id, Animation::RevealCreature));
+                    Ok(AxiomOutcome::Applied)
                 }
-                //commands.entity(entity_id).insert(Visibility::Hidden);
-                //world_map.anim_queue.push((entity_id, Animation::RevealCreature));
-            }
-            _ => ()
+                // Every Form marks the tiles it covers, and most Functions do nothing to an empty floor.
+                _ => Ok(AxiomOutcome::Fizzled),
+            };
+            let (outcome, error) = settle_outcome(&function, result);
+            axiom_events.send(AxiomEvent { caster: info.entity, target: AxiomTarget::Tile(coords), function, outcome, error, queued: Vec::new() });
+            continue;
         }
-    }
-
-    while !world_map.targeted_axioms.is_empty() {
-        anti_infinite_loop += 1;
-        if anti_infinite_loop > 500 { panic!("Infinite loop detected in axiom queue!") }
         let (entity, function, mut info) = world_map.targeted_axioms.pop().unwrap();
-        if let Ok((transform_source, mut species, mut breath, mut effects, _anim, mut pos, is_player)) = creatures.p0().get_mut(entity.to_owned()) {
-            //let (glamour, discipline, grace, pride) = (effects.status[0].stacks, effects.status[1].stacks,effects.status[2].stacks,effects.status[3].stacks);
-            assert_eq!(effects.status[0].effect_type, EffectType::Glamour);
-            assert_eq!(effects.status[1].effect_type, EffectType::Discipline);
-            assert_eq!(effects.status[2].effect_type, EffectType::Grace);
-            assert_eq!(effects.status[3].effect_type, EffectType::Pride);
-            let transform_source_trans = transform_source.translation;
-            let function = function.to_owned();
-            match function {
-                Function::Teleport { x, y } => {
-                    let (x, y) = if !is_in_bounds(x as i32, y as i32) {
-                        if !(y >= 0 && y < WORLD_HEIGHT as isize) {
-                            continue;
-                        }
-                        else if x < 0 {
-                            (45+x, y)
+        resolved.push(describe_function(&function));
+        let result = 'resolve: {
+            if let Ok((mut species, mut breath, mut effects, mut virtues, mut pos, is_player)) = creatures.p0().get_mut(entity.to_owned()) {
+                let source_pos = (pos.x, pos.y);
+                match function.clone() {
+                    Function::Teleport { x, y } => {
+                        let (x, y) = if !is_in_bounds(x as i32, y as i32) {
+                            if !(y >= 0 && y < WORLD_HEIGHT as isize) {
+                                break 'resolve Ok(AxiomOutcome::Fizzled);
+                            }
+                            else if x < 0 {
+                                (45+x, y)
+                            } else {
+                                (x % 45, y % 45)
+                            }
+                        } else {(x,y)};
+                        let (x, y) = (x as usize, y as usize);
+                        if world_map.entities[xy_idx(x, y)].is_some() {break 'resolve Ok(AxiomOutcome::Blocked);}
+
+                        //else if world_map.entities[xy_idx(x, y)].is_some() { // Cancel teleport if target is occupied
+                            //let collider = world_map.entities[xy_idx(x, y)].unwrap();
+                            //world_map.targeted_axioms.push((entity, Function::Collide { with: collider }, info.clone()));
+                            //continue;
+                        //}
+                        let old_pos = (pos.x, pos.y);
+                        let old_idx = xy_idx(pos.x, pos.y);
+                        (pos.x, pos.y) = (x, y);
+                        (pos.ox, pos.oy) = old_pos;
+                        let new_pos = (x, y);
+                        let dest = (pos.x as i32 -old_pos.0 as i32, pos.y as i32-old_pos.1 as i32);
+                        let idx = xy_idx(pos.x, pos.y);
+                        world_map.entities.swap(old_idx, idx);
+                        let max = dest.0.abs().max(dest.1.abs());
+
+                        // MOVING
+                        // ++Grace
+                        // --Discipline
+                        world_map.targeted_axioms.push((entity, Function::TriggerEffect { trig: TriggerType::Move, amount: max as usize }, info.clone()));
+
+                        assert!(!(dest.0 == 0 && dest.1 == 0));
+                        /*pos.momentum = if max == dest.0.abs(){ // Reassign the new momentum.
+                            (dest.0/dest.0.abs(), 0)
                         } else {
-                            (x % 45, y % 45)
+                            (0, dest.1/dest.1.abs())
+                        };*/
+
+                        //if anim.tweenable().progress() != 1.0 { continue; }
+                        if !is_player {
+                            break 'resolve Ok(AxiomOutcome::Applied);
                         }
-                    } else {(x,y)};
-                    let (x, y) = (x as usize, y as usize);
-                    if world_map.entities[xy_idx(x, y)].is_some() {continue;}
-
-                    //else if world_map.entities[xy_idx(x, y)].is_some() { // Cancel teleport if target is occupied
-                        //let collider = world_map.entities[xy_idx(x, y)].unwrap();
-                        //world_map.targeted_axioms.push((entity, Function::Collide { with: collider }, info.clone()));
-                        //continue;
-                    //}
-                    let old_pos = (pos.x, pos.y);
-                    let old_idx = xy_idx(pos.x, pos.y);
-                    (pos.x, pos.y) = (x, y);
-                    (pos.ox, pos.oy) = old_pos;
-                    let new_pos = (x, y);
-                    let dest = (pos.x as i32 -old_pos.0 as i32, pos.y as i32-old_pos.1 as i32);
-                    let idx = xy_idx(pos.x, pos.y);
-                    world_map.entities.swap(old_idx, idx);
-
-                    // MOVING
-                    // ++Grace
-                    // --Discipline
-                    world_map.targeted_axioms.push((entity, Function::TriggerEffect { trig: TriggerType::Move }, info.clone()));
-
-                    let max = dest.0.abs().max(dest.1.abs());
-                    assert!(!(dest.0 == 0 && dest.1 == 0));
-                    /*pos.momentum = if max == dest.0.abs(){ // Reassign the new momentum.
-                        (dest.0/dest.0.abs(), 0)
-                    } else {
-                        (0, dest.1/dest.1.abs())
-                    };*/
-
-                    //if anim.tweenable().progress() != 1.0 { continue; }
-                    if !is_player {
-                        continue;
-                    }
-                    else {
-                        let mut triggered = false;
-                        if is_player {
-                            for (passage_coords, destination) in &world_map.warp_zones{
-                                if new_pos == *passage_coords {
-                                    zoom.timer.unpause();
-                                    zoom.destination = destination.clone();
-                                    triggered = true;
-                                    break;
+                        else {
+                            let mut triggered = false;
+                            if is_player {
+                                for (passage_coords, destination) in &world_map.warp_zones{
+                                    if new_pos == *passage_coords {
+                                        zoom.timer.unpause();
+                                        zoom.destination = destination.clone();
+                                        triggered = true;
+                                        break;
+                                    }
                                 }
                             }
-                        }
-                        if triggered {world_map.anim_queue.push((entity, Animation::Passage))};
+                            if triggered {world_map.anim_queue.push((entity, Animation::Passage))};
 
+                        }
+                    },
+                    Function::AlterMomentum { alter } => {
+                        pos.momentum.0 += alter.0;
+                        pos.momentum.1 += alter.1;
                     }
-                },
-                Function::AlterMomentum { alter } => {
-                    pos.momentum.0 += alter.0;
-                    pos.momentum.1 += alter.1;
-                }
-                Function::ResetHorizontal => {
-                    pos.momentum.0 = 0;
-                }
-                Function::ResetVertical => {
-                    pos.momentum.1 = 0;
-                }
-                Function::FlatStealSouls { dam } => {
-                    let mut rng = rand::thread_rng();
-                    let mut payload = select_random_entities(&mut breath.discard, dam, &mut rng);
-                    if payload.len() < dam {
-                        payload.append(&mut select_random_entities(&mut breath.pile, dam, &mut rng));
+                    Function::ResetHorizontal => {
+                        pos.momentum.0 = 0;
+                    }
+                    Function::ResetVertical => {
+                        pos.momentum.1 = 0;
                     }
-                    if payload.len() < dam {
-                        while payload.len() < dam && !breath.held.is_empty(){
-                            payload.push(breath.held.pop().unwrap());
-                            if breath.held.is_empty() {
-                                breath.soulless = true;
-                                commands.entity(entity).insert(Wounded);
-                                world_map.anim_queue.push((entity, Animation::Soulless));
+                    Function::FlatStealSouls { dam } => {
+                        let mut payload = select_random_souls(&mut breath.discard, dam, &mut rng.combat);
+                        if payload.len() < dam {
+                            payload.append(&mut select_random_souls(&mut breath.pile, dam, &mut rng.combat));
+                        }
+                        if payload.len() < dam {
+                            while payload.len() < dam && !breath.held.is_empty(){
+                                payload.push(breath.held.pop().unwrap());
+                                if breath.held.is_empty() {
+                                    breath.soulless = true;
+                                    commands.entity(entity).insert(Wounded);
+                                    world_map.anim_queue.push((entity, Animation::Soulless));
+                                }
                             }
                         }
-                    }
+                        let stolen = payload.len();
 
-                    // TAKING DAMAGE
-                    // ++Discipline
-                    // --Pride
-                    world_map.targeted_axioms.push((entity, Function::TriggerEffect { trig: TriggerType::TakeDamage }, info.clone()));
+                        // TAKING DAMAGE
+                        // ++Discipline
+                        // --Pride
+                        world_map.targeted_axioms.push((entity, Function::TriggerEffect { trig: TriggerType::TakeDamage, amount: stolen }, info.clone()));
 
-                    // DEALING DAMAGE
-                    // ++Pride
-                    // --Glamour
-                    world_map.targeted_axioms.push((info.entity, Function::TriggerEffect { trig: TriggerType::DealDamage }, info.clone()));
+                        // DEALING DAMAGE
+                        // ++Pride
+                        // --Glamour
+                        world_map.targeted_axioms.push((info.entity, Function::TriggerEffect { trig: TriggerType::DealDamage, amount: stolen }, info.clone()));
 
                     
-                    if let Ok((transform_culprit, _species, mut breath_culprit, _ax, _anim, _pos, _is_player)) = creatures.p0().get_mut(info.entity.to_owned()) {
-                        let mut anim_output = Vec::new();
-                        for soul in payload{
-                            let slot = if let Ok((_anim, _transform, _sprite, soul_id), ) = souls.get(soul) { match_soul_with_display_index(soul_id) } else { panic!("A stolen soul does not exist!")};
-                            breath_culprit.discard[slot].push(soul);
-                            breath_culprit.soulless = false;
-                            commands.entity(info.entity).remove::<Wounded>();
-                            anim_output.push(soul);
+                        if let Ok((_species, mut breath_culprit, _ax, _virtues, pos_culprit, _is_player)) = creatures.p0().get_mut(info.entity.to_owned()) {
+                            let mut anim_output = Vec::new();
+                            for soul in payload{
+                                let slot = match_soul_with_display_index(&soul);
+                                breath_culprit.discard[slot].push(soul);
+                                breath_culprit.soulless = false;
+                                commands.entity(info.entity).remove::<Wounded>();
+                                anim_output.push(soul);
+                            }
+                            world_map.anim_queue.push((entity, Animation::SoulDrain { source: source_pos, destination: (pos_culprit.x, pos_culprit.y), drained: anim_output }));
                         }
-                        world_map.anim_queue.push((entity, Animation::SoulDrain { source: transform_source_trans, destination: transform_culprit.translation, drained: anim_output }));
-                    }
 
-                },
-                Function::TriggerEffect { trig } => {
-                    let mut remove_these_effects = Vec::new();
-                    for (i, eff) in effects.status.iter_mut().enumerate() {
-                        if match_effect_with_decay(&eff.effect_type) == trig {
-                            eff.stacks = reduce_down_to(match_effect_with_minimum(&eff.effect_type), eff.stacks, 1);
-                        }
-                        if match_effect_with_gain(&eff.effect_type) == trig {
-                            eff.stacks += 1;
-                        }
-                        if eff.stacks == 0 {
-                            match &eff.effect_type {
-                                EffectType::Possession { link } => {
-                                    world_map.targeted_axioms.push((*link, Function::SwapAnchor, info.clone()));
-                                },
-                                EffectType::Polymorph { original } => {
-                                    world_map.targeted_axioms.push((entity, Function::PolymorphNow { new_species: original.clone() }, info.clone()));
-                                }
-                                EffectType::Charm { original } => {
-                                    commands.entity(entity).insert(original.clone());
-                                }
-                                EffectType::OpenDoor => {
-                                    match world_map.entities[xy_idx(pos.x, pos.y)] {
-                                        Some(_) => {
-                                            world_map.targeted_axioms.push((entity, Function::ApplyEffect { effect: Effect {stacks: 1, effect_type: EffectType::OpenDoor}}, info.clone()));
-                                        }
-                                        None => {
-                                            world_map.targeted_axioms.push((entity, Function::BecomeTangible, info.clone()));
-                                            match *species {
-                                                Species::Airlock { dir } => world_map.anim_queue.push((entity, Animation::UseDoor { orient: dir, closing: true })),
-                                                _ => ()
-                                            }
-                                        }
+                    },
+                    Function::TriggerEffect { trig, amount } => {
+                        if trig == TriggerType::EachTurn {
+                            let holder = CasterInfo::new(entity, &pos, &species, &virtues, &effects.status, is_player);
+                            for eff in effects.status.iter_mut() {
+                                if let EffectType::Link { axiom, targets } = &mut eff.effect_type {
+                                    let (form, linked) = (**axiom).clone();
+                                    let hit = grab_coords_from_form(&world_map.entities, form, holder.clone(), &census);
+                                    for target in hit.entities.iter() {
+                                        world_map.targeted_axioms.push((*target, linked.clone(), holder.clone()));
                                     }
-
+                                    for square in hit.coords {
+                                        world_map.anim_queue.push((entity, Animation::FormMark { coords: square }));
+                                        world_map.floor_axioms.push((square, linked.clone(), holder.clone()));
+                                    }
+                                    *targets = hit.entities;
+                                }
+                            }
+                        }
+                        virtues.trigger(&trig);
+                        let mut remove_these_effects = Vec::new();
+                        let mut hooks = Vec::new();
+                        for (i, eff) in effects.status.iter_mut().enumerate() {
+                            let definition = match_effect_with_definition(&eff.effect_type);
+                            if definition.decay == trig {
+                                eff.stacks = reduce_down_to(definition.minimum, eff.stacks, 1);
+                                hooks.extend(definition.on_tick);
+                            }
+                            if definition.gain == trig {
+                                eff.stacks += 1;
+                            }
+                            if eff.stacks == 0 {
+                                hooks.extend(definition.on_expire);
+                                remove_these_effects.push(i);
+                            }
+                            else if let Some((limit, overflow)) = definition.overflow {
+                                if eff.stacks > limit {
+                                    hooks.extend(overflow);
+                                    remove_these_effects.push(i);
                                 }
-                                _ => (),
                             }
-                            remove_these_effects.push(i);
                         }
-                        if eff.effect_type == EffectType::Meltdown && eff.stacks > 9 {
-                            world_map.targeted_axioms.push((entity, Function::BlinkOuter, info.clone()));
-                            remove_these_effects.push(i);
+                        for i in remove_these_effects.into_iter().rev() {
+                            effects.status.remove(i);
+                        }
+                        for hook in hooks {
+                            world_map.targeted_axioms.push((entity, hook, info.clone()));
+                        }
+                        if let Some((form, reaction)) = reactive.get(entity).ok().and_then(|reactive| reactive.reactions.get(&trig)) {
+                            let reactor = CasterInfo::new(entity, &pos, &species, &virtues, &effects.status, is_player);
+                            let reaction = fill_trigger_amount(reaction.clone(), amount);
+                            let targets = grab_coords_from_form(&world_map.entities, form.clone(), reactor.clone(), &census);
+                            for target in targets.entities {
+                                world_map.targeted_axioms.push((target, reaction.clone(), reactor.clone()));
+                            }
+                            for square in targets.coords {
+                                world_map.anim_queue.push((entity, Animation::FormMark { coords: square }));
+                                world_map.floor_axioms.push((square, reaction.clone(), reactor.clone()));
+                            }
                         }
                     }
-                    for i in remove_these_effects{
-                        effects.status.remove(i);
-                    }
-                }
-                Function::ApplyEffect { effect } => {
-                    let mut found_same_effect = false;
-                    for eff in effects.status.iter_mut() {
-                        if eff.effect_type == effect.effect_type {
-                            eff.stacks += effect.stacks;
-                            found_same_effect = true;
-                            break;
+                    Function::ApplyEffect { effect } => {
+                        if virtues.add(&effect.effect_type, effect.stacks) { break 'resolve Ok(AxiomOutcome::Applied); }
+                        let definition = match_effect_with_definition(&effect.effect_type);
+                        match effects.status.iter_mut().find(|eff| eff.effect_type.is_same_as(&effect.effect_type)) {
+                            Some(eff) => match definition.stacking {
+                                Stacking::Add => eff.stacks += effect.stacks,
+                                Stacking::Refresh => eff.stacks = effect.stacks,
+                                Stacking::Max => eff.stacks = eff.stacks.max(effect.stacks),
+                                Stacking::Unique => break 'resolve Ok(AxiomOutcome::Fizzled),
+                            },
+                            None => effects.status.push(effect),
+                        }
+                        for hook in definition.on_apply {
+                            world_map.targeted_axioms.push((entity, hook, info.clone()));
+                        }
+                    },
+                    Function::AimAt { target, of } => {
+                        world_map.targeted_axioms.push((target, *of, info.clone()));
+                    },
+                    Function::SetFaction { faction } => {
+                        commands.entity(entity).insert(faction);
+                    },
+                    Function::CloseDoor => {
+                        match world_map.entities[xy_idx(pos.x, pos.y)] {
+                            Some(_) => {
+                                world_map.targeted_axioms.push((entity, Function::ApplyEffect { effect: Effect {stacks: 1, effect_type: EffectType::OpenDoor}}, info.clone()));
+                            }
+                            None => {
+                                world_map.targeted_axioms.push((entity, Function::BecomeTangible, info.clone()));
+                                if let Species::Airlock { dir } = *species {
+                                    world_map.anim_queue.push((entity, Animation::UseDoor { orient: dir, closing: true }));
+                                }
+                            }
                         }
+                    },
+                    Function::StealSouls => {
+                        world_map.targeted_axioms.push((entity, Function::FlatStealSouls { dam: info.virtues.pride }, info.clone()));
                     }
-                    if !found_same_effect {
-                        effects.status.push(effect);
+                    Function::PossessCreature => {
+                        let duration = 999;//info.virtues.glamour;
+                        world_map.targeted_axioms.push((entity, Function::SwapAnchor, info.clone()));
+                        world_map.targeted_axioms.push((entity, Function::ApplyEffect { effect: Effect {stacks: duration, effect_type: EffectType::Possession { link: info.entity }}}, info.clone()));
                     }
-                },
-                Function::StealSouls => {
-                    world_map.targeted_axioms.push((entity, Function::FlatStealSouls { dam: info.pride }, info.clone()));
-                }
-                Function::PossessCreature => {
-                    let duration = 999;//info.glamour;
-                    world_map.targeted_axioms.push((entity, Function::SwapAnchor, info.clone()));
-                    world_map.targeted_axioms.push((entity, Function::ApplyEffect { effect: Effect {stacks: duration, effect_type: EffectType::Possession { link: info.entity }}}, info.clone()));
-                }
-                Function::Synchronize => {
-                    let duration = 10;//info.grace;
-                    world_map.targeted_axioms.push((entity, Function::ApplyEffect { effect: Effect {stacks: duration, effect_type: EffectType::Sync { link: info.entity }}}, info.clone()));
-                }
-                Function::Charm {dur}=> {
-                    let fac = faction.get(entity).unwrap();
-                    let new_fac = faction.get(info.entity).unwrap();
-                    world_map.targeted_axioms.push((entity, Function::ApplyEffect { effect: Effect {stacks: dur, effect_type: EffectType::Charm { original: fac.clone()}}}, info.clone()));
-                    commands.entity(entity).insert(new_fac.clone());
-                }
-                Function::MarkPatient => {
-                    world_map.targeted_axioms.push((entity, Function::MomentumReverseDash, info.clone()));
-                    world_map.targeted_axioms.push((info.entity, Function::ApplyEffect { effect: Effect { stacks: 99, effect_type: EffectType::AssignedPatient { link: entity } } }, info.clone()));
-                }
-                Function::InjectCaste {num, caste} => {
-                    let mut payload = Vec::with_capacity(num);
-                    let slot = match_soul_with_display_index(&caste);
-                    let mut origin_translation = Vec3::ZERO;
-
-                    if let Ok((transform_culprit, _species, mut breath_culprit, _ax, _anim, _pos, _is_player)) = creatures.p0().get_mut(info.entity.to_owned()) {
-                        origin_translation = transform_culprit.translation;
-                        while payload.len() < num {
-                            if !breath_culprit.discard[slot].is_empty() {
-                                let soul = breath_culprit.discard[slot].pop().unwrap();
-                                payload.push(soul);
-                            } else if !breath_culprit.pile[slot].is_empty() {
-                                let soul = breath_culprit.pile[slot].pop().unwrap();
-                                payload.push(soul);
-                            } else if !breath_culprit.held.is_empty() {
-                                let soul = breath_culprit.held.pop().unwrap();
-                                let soul_type = if let Ok((_anim, _transform, _sprite, soul_id), ) = souls.get(soul) {soul_id } else { panic!("A stolen soul does not exist!")};
-                                if soul_type == &caste {
+                    Function::Synchronize => {
+                        let duration = 10;//info.virtues.grace;
+                        world_map.targeted_axioms.push((entity, Function::ApplyEffect { effect: Effect {stacks: duration, effect_type: EffectType::Sync { link: info.entity }}}, info.clone()));
+                    }
+                    Function::Charm {dur}=> {
+                        let Ok(fac) = faction.get(entity) else { break 'resolve Err(AxiomError::MissingCreature(entity)) };
+                        let Ok(new_fac) = faction.get(info.entity) else { break 'resolve Err(AxiomError::MissingCreature(info.entity)) };
+                        world_map.targeted_axioms.push((entity, Function::ApplyEffect { effect: Effect {stacks: dur, effect_type: EffectType::Charm { original: fac.clone()}}}, info.clone()));
+                        commands.entity(entity).insert(new_fac.clone());
+                    }
+                    Function::MarkPatient => {
+                        world_map.targeted_axioms.push((entity, Function::MomentumReverseDash, info.clone()));
+                        world_map.targeted_axioms.push((info.entity, Function::ApplyEffect { effect: Effect { stacks: 99, effect_type: EffectType::AssignedPatient { link: entity } } }, info.clone()));
+                    }
+                    Function::InjectCaste {num, caste} => {
+                        let mut payload = Vec::with_capacity(num);
+                        let slot = match_soul_with_display_index(&caste);
+                        let mut origin_pos = info.pos;
+
+                        if let Ok((_species, mut breath_culprit, _ax, _virtues, pos_culprit, _is_player)) = creatures.p0().get_mut(info.entity.to_owned()) {
+                            origin_pos = (pos_culprit.x, pos_culprit.y);
+                            while payload.len() < num {
+                                if !breath_culprit.discard[slot].is_empty() {
+                                    let soul = breath_culprit.discard[slot].pop().unwrap();
                                     payload.push(soul);
+                                } else if !breath_culprit.pile[slot].is_empty() {
+                                    let soul = breath_culprit.pile[slot].pop().unwrap();
+                                    payload.push(soul);
+                                } else if let Some(index) = breath_culprit.held.iter().rposition(|soul| soul == &caste) {
+                                    payload.push(breath_culprit.held.remove(index));
                                 } else {
-                                    breath_culprit.held.push(soul);
+                                    if breath_culprit.held.is_empty() {
+                                        breath_culprit.soulless = true;
+                                        commands.entity(info.entity).insert(Wounded);
+                                    }
+                                    break;
                                 }
-                            } else {
-                                breath_culprit.soulless = true;
-                                commands.entity(info.entity).insert(Wounded);
-                                break;
                             }
                         }
-                    }
-                    if let Ok((transform_receiver, _species, mut breath_receiver, _ax, _anim, _pos, _is_player)) = creatures.p0().get_mut(entity) {
-                        let mut anim_output = Vec::new();
-                        for i in payload {
-                            breath_receiver.discard[slot].push(i);
-                            anim_output.push(i);
-                            breath_receiver.soulless = false;
-                            commands.entity(entity).remove::<Wounded>();
+                        let injected = payload.len();
+                        if let Ok((_species, mut breath_receiver, _ax, _virtues, pos_receiver, _is_player)) = creatures.p0().get_mut(entity) {
+                            let mut anim_output = Vec::new();
+                            for i in payload {
+                                breath_receiver.discard[slot].push(i);
+                                anim_output.push(i);
+                                breath_receiver.soulless = false;
+                                commands.entity(entity).remove::<Wounded>();
+                            }
+                            world_map.anim_queue.push((entity, Animation::SoulDrain { source: origin_pos, destination: (pos_receiver.x, pos_receiver.y), drained: anim_output }));
                         }
-                        world_map.anim_queue.push((entity, Animation::SoulDrain { source: origin_translation, destination: transform_receiver.translation, drained: anim_output }));
-                    }
 
-                    // TAKING DAMAGE
-                    // ++Discipline
-                    // --Pride
-                    world_map.targeted_axioms.push((info.entity, Function::TriggerEffect { trig: TriggerType::TakeDamage }, info.clone()));
+                        // TAKING DAMAGE
+                        // ++Discipline
+                        // --Pride
+                        world_map.targeted_axioms.push((info.entity, Function::TriggerEffect { trig: TriggerType::TakeDamage, amount: injected }, info.clone()));
 
-                    // DEALING DAMAGE
-                    // ++Pride
-                    // --Glamour
-                    world_map.targeted_axioms.push((entity, Function::TriggerEffect { trig: TriggerType::DealDamage }, info.clone()));
+                        // DEALING DAMAGE
+                        // ++Pride
+                        // --Glamour
+                        world_map.targeted_axioms.push((entity, Function::TriggerEffect { trig: TriggerType::DealDamage, amount: injected }, info.clone()));
 
-                }
-                Function::CyanCharm => {
-                    let dur = 10;//info.pride;
-                    world_map.targeted_axioms.push((entity, Function::InjectCaste {num: 1, caste: Soul::Serene}, info.clone()));
-                    world_map.targeted_axioms.push((entity, Function::Charm {dur}, info.clone()));
-                }
-                Function::Segmentize => {
-                    world_map.targeted_axioms.push((entity, Function::PolymorphNow { new_species: Species::EpsilonTail { order: -1 } }, info.clone()));
-                    commands.entity(entity).remove::<Wounded>();
-                    commands.entity(entity).insert(Segmentified);
-                }
-                Function::ImitateSpecies => {
-                    let duration = info.grace;
-                    world_map.targeted_axioms.push((info.entity, Function::PolymorphNow { new_species: species.clone() }, info.clone()));
-                    world_map.targeted_axioms.push((info.entity, Function::ApplyEffect { effect: Effect {stacks: duration, effect_type: EffectType::Polymorph { original: info.species.clone() }}}, info.clone()));
-                }
-                Function::SwapSpecies => {
-                    let duration = info.grace;
-                    world_map.targeted_axioms.push((info.entity, Function::PolymorphNow { new_species: species.clone() }, info.clone()));
-                    world_map.targeted_axioms.push((info.entity, Function::ApplyEffect { effect: Effect {stacks: duration, effect_type: EffectType::Polymorph { original: info.species.clone() }}}, info.clone()));
-                    world_map.targeted_axioms.push((entity, Function::PolymorphNow { new_species: info.species.clone() }, info.clone()));
-                    world_map.targeted_axioms.push((entity, Function::ApplyEffect { effect: Effect {stacks: duration, effect_type: EffectType::Polymorph { original: species.clone() }}}, info.clone()));
-                }
-                Function::PolymorphNow { new_species } => {
-                    *species = new_species.clone();
-                    world_map.anim_queue.push((entity, Animation::Polymorph {new_species}));
-                }
-                Function::BlinkOuter => {
-                    let dests = grab_coords_from_form(&world_map.entities, Form::BigOuter, info.clone());
-                    for target in dests.coords {
-                        if world_map.entities[xy_idx(target.0, target.1)].is_none() {
-                            world_map.targeted_axioms.push((entity, Function::Teleport { x: target.0 as isize, y: target.1 as isize}, info.clone()));
-                            break;
+                    }
+                    Function::CyanCharm => {
+                        let dur = 10;//info.virtues.pride;
+                        world_map.targeted_axioms.push((entity, Function::Sequence(vec![Function::InjectCaste {num: 1, caste: Soul::Serene}, Function::Charm {dur}]), info.clone()));
+                    }
+                    Function::Segmentize => {
+                        world_map.targeted_axioms.push((entity, Function::PolymorphNow { new_species: Species::EpsilonTail { order: -1 } }, info.clone()));
+                        commands.entity(entity).remove::<Wounded>();
+                        commands.entity(entity).insert(Segmentified);
+                    }
+                    Function::ImitateSpecies => {
+                        let duration = info.virtues.grace;
+                        world_map.targeted_axioms.push((info.entity, Function::PolymorphNow { new_species: species.clone() }, info.clone()));
+                        world_map.targeted_axioms.push((info.entity, Function::ApplyEffect { effect: Effect {stacks: duration, effect_type: EffectType::Polymorph { original: info.species.clone() }}}, info.clone()));
+                    }
+                    Function::SwapSpecies => {
+                        let duration = info.virtues.grace;
+                        world_map.targeted_axioms.push((info.entity, Function::PolymorphNow { new_species: species.clone() }, info.clone()));
+                        world_map.targeted_axioms.push((info.entity, Function::ApplyEffect { effect: Effect {stacks: duration, effect_type: EffectType::Polymorph { original: info.species.clone() }}}, info.clone()));
+                        world_map.targeted_axioms.push((entity, Function::PolymorphNow { new_species: info.species.clone() }, info.clone()));
+                        world_map.targeted_axioms.push((entity, Function::ApplyEffect { effect: Effect {stacks: duration, effect_type: EffectType::Polymorph { original: species.clone() }}}, info.clone()));
+                    }
+                    Function::PolymorphNow { new_species } => {
+                        *species = new_species.clone();
+                        world_map.anim_queue.push((entity, Animation::Polymorph {new_species}));
+                    }
+                    Function::BlinkOuter => {
+                        let dests = grab_coords_from_form(&world_map.entities, Form::BigOuter, info.clone(), &census);
+                        for target in dests.coords {
+                            if world_map.entities[xy_idx(target.0, target.1)].is_none() {
+                                world_map.targeted_axioms.push((entity, Function::Teleport { x: target.0 as isize, y: target.1 as isize}, info.clone()));
+                                break;
+                            }
                         }
                     }
-                }
-                Function::MomentumDash => {
-                    world_map.targeted_axioms.push((entity, Function::FlatMomentumDash { dist: info.grace }, info.clone()));
-                }
-                Function::Collide { with } => { // with is the entity you hit with your move
-                    let coll_species = creatures.p2().get(with).unwrap().clone();
-                    let coll_pos = creatures.p1().get(with).map(|e| (e.x, e.y)).unwrap();
-                    let wound = check_wound.get(with);
-                    if is_pushable(&coll_species) || wound.is_ok() {
-                        if world_map.entities[xy_idx((coll_pos.0 as i32 + info.momentum.0) as usize, (coll_pos.1 as i32 + info.momentum.1) as usize)].is_some() {continue;}
-                        world_map.targeted_axioms.push((entity, Function::FlatMomentumDash { dist: 1 }, info.clone()));
-                        world_map.targeted_axioms.push((with, Function::FlatMomentumDash { dist: 1 }, info.clone()));
+                    Function::MomentumDash => {
+                        world_map.targeted_axioms.push((entity, Function::FlatMomentumDash { dist: info.virtues.grace }, info.clone()));
                     }
-                    if is_openable(&coll_species) {
-                        world_map.targeted_axioms.push((with, Function::BecomeIntangible, info.clone()));
-                        world_map.targeted_axioms.push((with, Function::ApplyEffect { effect: Effect {stacks: 3, effect_type: EffectType::OpenDoor}}, info.clone()));
-                        match &coll_species {
-                            Species::Airlock { dir } => {
+                    Function::Collide { with } => { // with is the entity you hit with your move
+                        let program = species.clone();
+                        let Ok(coll_species) = creatures.p2().get(with).map(|(_, species, _)| species.clone()) else { break 'resolve Err(AxiomError::MissingCreature(with)) };
+                        let Ok(coll_pos) = creatures.p1().get(with).map(|e| (e.x, e.y)) else { break 'resolve Err(AxiomError::MissingCreature(with)) };
+                        let wound = check_wound.get(with);
+                        // A crate driven into an activator rewrites that caste's Axiom in the creature next to it, or in whoever drove it there.
+                        if let (Species::CrateActivator { caste }, Species::FormCrate { .. } | Species::FunctionCrate { .. }) = (&coll_species, &program) {
+                            let target = get_neighbouring_entities(&world_map.entities, coll_pos.0, coll_pos.1).into_iter().flatten()
+                                .find(|neighbour| *neighbour != entity && creatures.p2().get(*neighbour).is_ok_and(|(_, species, _)| !is_soulless(species)))
+                                .unwrap_or(info.entity);
+                            world_map.targeted_axioms.push((target, Function::Reprogram { caste: *caste, program: Box::new(program), from: entity }, info.clone()));
+                        }
+                        if is_pushable(&coll_species) || wound.is_ok() {
+                            if let Some(blocker) = world_map.entities[xy_idx((coll_pos.0 as i32 + info.momentum.0) as usize, (coll_pos.1 as i32 + info.momentum.1) as usize)] {
+                                if creatures.p2().get(blocker).is_ok_and(|(_, species, _)| matches!(species, Species::CrateActivator { .. })) {
+                                    world_map.targeted_axioms.push((with, Function::Collide { with: blocker }, info.clone()));
+                                }
+                                break 'resolve Ok(AxiomOutcome::Blocked);
+                            }
+                            world_map.targeted_axioms.push((entity, Function::FlatMomentumDash { dist: 1 }, info.clone()));
+                            world_map.targeted_axioms.push((with, Function::FlatMomentumDash { dist: 1 }, info.clone()));
+                        }
+                        if is_openable(&coll_species) {
+                            world_map.targeted_axioms.push((with, Function::BecomeIntangible, info.clone()));
+                            world_map.targeted_axioms.push((with, Function::ApplyEffect { effect: Effect {stacks: 3, effect_type: EffectType::OpenDoor}}, info.clone()));
+                            if let Species::Airlock { dir } = &coll_species {
                                 world_map.anim_queue.push((with, Animation::UseDoor { orient: *dir, closing: false }));
                                 let corner = {
                                     let curr = ((coll_pos.0/9)*9, (coll_pos.1/9)*9);
@@ -927,230 +987,207 @@ fn dispense_functions(
                                 for bui in builds {
                                     world_map.floor_axioms.push((bui.1, Function::SummonCreature { species: bui.0 }, info.clone()));
                                 }
-                            },
-                            _ => ()
-                        }
-                    }
-                },
-                Function::BecomeIntangible => {
-                    let idx = xy_idx(pos.x, pos.y);
-                    world_map.entities[idx] = None;
-                }
-                Function::BecomeTangible => {
-                    let idx = xy_idx(pos.x, pos.y);
-                    world_map.entities[idx] = Some(entity);
-                }
-                Function::MessageLog { message_id } => {
-                    events.send(LogMessage(message_id));
-                    world_map.anim_queue.push((entity, Animation::MessagePrint));
-                }
-                Function::SwapAnchor => {
-                    if !is_player {
-                        if let Ok((_transform_culprit, _species, _breath_culprit, _ax,  _anim, _pos, is_player_cul)) = creatures.p0().get_mut(info.entity.to_owned()) {
-                            if is_player_cul{
-                                commands.entity(info.entity).remove::<RealityAnchor>();
-                                commands.entity(entity).insert(RealityAnchor{player_id: 0});
-                                world_map.anim_queue.push((entity, Animation::SoulSwap));
-                                current_crea_display.entity = entity;
                             }
                         }
-                    } else {
-                        commands.entity(entity).remove::<RealityAnchor>();
-                        commands.entity(info.entity).insert(RealityAnchor{player_id: 0});
-                        world_map.anim_queue.push((entity, Animation::SoulSwap));
-                        current_crea_display.entity = info.entity;
+                    },
+                    Function::Reprogram { caste, program, from } => {
+                        let Some(slot) = effects.axioms.get_mut(caste) else { break 'resolve Ok(AxiomOutcome::Fizzled) };
+                        // The crate takes back whatever it replaced, so pushing it in again undoes the change.
+                        let undo = match *program {
+                            Species::FormCrate { form } => Species::FormCrate { form: mem::replace(&mut slot.0, form) },
+                            Species::FunctionCrate { function } => Species::FunctionCrate { function: Box::new(mem::replace(&mut slot.1, *function)) },
+                            _ => break 'resolve Ok(AxiomOutcome::Fizzled),
+                        };
+                        world_map.targeted_axioms.push((from, Function::PolymorphNow { new_species: undo }, info.clone()));
+                        world_map.anim_queue.push((entity, Animation::FormMark { coords: (pos.x, pos.y) }));
+                        notices.send(LogNotice(format!("The [l]{:?}[w] Axiom of the [y]{}[w] has been rewritten.", match_axiom_with_soul(caste), match_species_with_name(&species))));
                     }
-                }
-                Function::Coil => {
-                    let atk_pos = creatures.p1().get(info.entity).map(|e| (e.x, e.y)).unwrap();
-                    let adj = get_neighbouring_entities(&world_map.entities, atk_pos.0, atk_pos.1);
-                    let count = adj.iter().filter(|&x| x.is_some()).count();
-                    world_map.targeted_axioms.push((entity, Function::FlatStealSouls { dam: info.pride*count }, info.clone()));
-                }
-                Function::Duplicate => {
-                    let adj = get_empty_neighbours(&world_map.entities, pos.x, pos.y);
-                    let mut rng = thread_rng();
-                    let dup_loc = adj.choose(&mut rng);
-                    if dup_loc.is_some() {
-                        world_map.floor_axioms.push((*dup_loc.unwrap(), Function::SummonCreature { species: species.clone() }, info.clone()));
+                    Function::BecomeIntangible => {
+                        let idx = xy_idx(pos.x, pos.y);
+                        world_map.entities[idx] = None;
                     }
-                }
-                Function::RedirectSouls { dam, dest } => {
-                    let new_info = if let Ok((_transform_source, species, _breath, ax, _anim, pos, is_player)) = creatures.p0().get(dest) {
-                        let (glamour, discipline, grace, pride) = (ax.status[0].stacks, ax.status[1].stacks,ax.status[2].stacks,ax.status[3].stacks);
-                        CasterInfo{entity: dest, pos: (pos.x, pos.y), species: species.clone(), momentum: pos.momentum, is_player, glamour, grace, discipline, pride, effects: ax.status.clone()}
-                    } else { panic!("The RedirectSouls's destination entity does not exist!")};
-                    world_map.targeted_axioms.push((entity, Function::FlatStealSouls { dam }, new_info));
-                },
-                Function::Dash { dx, dy } => {
-                    let dest = (dx, dy);
-                    let mut line = bresenham_line(pos.x as i32, pos.y as i32, pos.x as i32 + dest.0, pos.y as i32 + dest.1);
-                    line.remove(0); // remove the origin point
-                    let (mut fx, mut fy) = (pos.x as isize, pos.y as isize);
-                    for (nx, ny) in line {
-                        let (x, y) = if !is_in_bounds(nx as i32, ny as i32) {
-                            if !(ny >= 0 && ny < WORLD_HEIGHT as i32) {
-                                continue;
-                            }
-                            else if nx < 0 {
-                                (45+nx, ny)
-                            } else {
-                                (nx % 45, ny % 45)
+                    Function::BecomeTangible => {
+                        let idx = xy_idx(pos.x, pos.y);
+                        world_map.entities[idx] = Some(entity);
+                    }
+                    Function::MessageLog { message_id } => {
+                        events.send(LogMessage(message_id));
+                        world_map.anim_queue.push((entity, Animation::MessagePrint));
+                    }
+                    Function::SwapAnchor => {
+                        if !is_player {
+                            if let Ok((_species, _breath_culprit, _ax, _virtues, _pos, is_player_cul)) = creatures.p0().get_mut(info.entity.to_owned()) {
+                                if is_player_cul{
+                                    commands.entity(info.entity).remove::<RealityAnchor>();
+                                    commands.entity(entity).insert(RealityAnchor{player_id: 0});
+                                    world_map.anim_queue.push((entity, Animation::SoulSwap));
+                                    current_crea_display.entity = entity;
+                                }
                             }
-                        } else {(nx,ny)};
-                        if world_map.entities[xy_idx(x as usize, y as usize)].is_some() { // TODO replace this with world wrap
-                            // TODO Raise a collision event here
-                            let collider = world_map.entities[xy_idx(nx as usize, ny as usize)].unwrap(); // This also has problems
-                            world_map.targeted_axioms.push((entity, Function::Collide { with: collider }, info.clone()));
-                            break;
+                        } else {
+                            commands.entity(entity).remove::<RealityAnchor>();
+                            commands.entity(info.entity).insert(RealityAnchor{player_id: 0});
+                            world_map.anim_queue.push((entity, Animation::SoulSwap));
+                            current_crea_display.entity = info.entity;
                         }
-                        else {(fx, fy) = (nx as isize, ny as isize)}
                     }
-                    world_map.targeted_axioms.push((entity, Function::Teleport { x: fx, y: fy }, info.clone()));
-                },
-                Function::FlatMomentumDash { dist } => {
-                    let dest = (dist as i32 * info.momentum.0, dist as i32 * info.momentum.1);
-                    world_map.targeted_axioms.push((entity, Function::Dash { dx: dest.0, dy: dest.1 }, info.clone()));
-                },
-                Function::MomentumSlamDash { dist } => {
-                    world_map.targeted_axioms.push((entity, Function::MeleeSlam { dist }, info.clone()));
-                    world_map.targeted_axioms.push((entity, Function::FlatMomentumDash { dist }, info.clone()));
-                },
-                Function::MeleeSlam { dist } => {
-                    let coll_pos = creatures.p1().get(info.entity).map(|e| (e.x, e.y)).unwrap();
-                    info.pos = coll_pos;
-                    let targets = grab_coords_from_form(&world_map.entities, Form::MomentumTouch, info.clone());
-                    for target in targets.entities {
-                        world_map.targeted_axioms.push((target, Function::FlatMomentumDash { dist }, info.clone()));
+                    Function::Coil => {
+                        let Ok(atk_pos) = creatures.p1().get(info.entity).map(|e| (e.x, e.y)) else { break 'resolve Err(AxiomError::MissingCreature(info.entity)) };
+                        let adj = get_neighbouring_entities(&world_map.entities, atk_pos.0, atk_pos.1);
+                        let count = adj.iter().filter(|&x| x.is_some()).count();
+                        world_map.targeted_axioms.push((entity, Function::FlatStealSouls { dam: info.virtues.pride*count }, info.clone()));
                     }
-                }
-                Function::MomentumReverseDash => {
-                    let dist = info.grace;
-                    let dest = (dist as i32 * -info.momentum.0, dist as i32 * -info.momentum.1);
-                    world_map.targeted_axioms.push((entity, Function::Dash { dx: dest.0, dy: dest.1 }, info.clone()));
-                },
-                Function::DiscardSoul { soul, slot } => {
-                    if let Ok((mut anim, transform, _sprite, soul_id), ) = souls.get_mut(soul) { 
-                        // Move the soul to the discard.
-                        breath.discard[match_soul_with_display_index(&soul_id)].push(soul);
-                        let index = breath.discard[match_soul_with_display_index(&soul_id)].iter().position(|&ent| ent == soul);
-                        let final_pos = get_soul_rot_position(&soul_id, (ui_center.x, ui_center.y), true, time.timer.elapsed_secs()+0.5, index.unwrap());
-                        let tween_tr = Tween::new(
-                            EaseFunction::QuadraticInOut,
-                            Duration::from_millis(500),
-                            TransformPositionLens {
-                                start: transform.translation,
-                                end: Vec3{ x: final_pos.0, y: final_pos.1, z: 0.5},
-                            },
-                        );
-                        let tween_sc = Tween::new(
-                            EaseFunction::QuadraticInOut,
-                            Duration::from_millis(500),
-                            TransformScaleLens {
-                                start: transform.scale,
-                                end: Vec3{ x: transform.scale.x/3., y: transform.scale.y/3., z: 0.},
-                            },
-                        );
-                        let track = Tracks::new([tween_tr, tween_sc]);
-                        anim.set_tweenable(track);
+                    Function::Duplicate => {
+                        let adj = get_empty_neighbours(&world_map.entities, pos.x, pos.y);
+                        if let Some(dup_loc) = adj.choose(&mut rng.combat) {
+                            world_map.floor_axioms.push((*dup_loc, Function::SummonCreature { species: species.clone() }, info.clone()));
+                        }
                     }
-                    let mut rng = rand::thread_rng();
-                    let mut possible_indices = Vec::new();
-                    for (i, vec) in breath.pile.iter().enumerate() { if !vec.is_empty() {possible_indices.push(i)}}
-                    if possible_indices.is_empty() {
-                        let mut found_convert = None;
-                        let mut harmonized = Some([Soul::Vile,Soul::Feral,Soul::Saintly,Soul::Ordered].choose(&mut rng).unwrap()); // TODO it should target slots with remaining souls only?
-                        if breath.discard[0].is_empty() {harmonized = None;}
-                        for j in breath.discard.iter() { // Reshuffle if no souls are left!
-                            for i in j.iter(){
-                                if let Ok((mut anim, transform, mut sprite, mut soul_id), ) = souls.get_mut(*i) {
-                                    let index = breath.discard[match_soul_with_display_index(&soul_id)].iter().position(|&ent| ent == *i);
-                                    if harmonized.is_some_and(|har| har.clone() == soul_id.clone()) {
-                                        found_convert = Some((i,soul_id.clone()));
-                                        *soul_id = Soul::Serene;
-                                        sprite.index = match_soul_with_sprite(&soul_id);
-                                        harmonized = None;
-                                    }
-                                    let final_pos = get_soul_rot_position(&soul_id, (ui_center.x, ui_center.y), false, time.timer.elapsed_secs()+0.5, index.unwrap());
-                                    let tween_tr = Tween::new(
-                                        EaseFunction::QuadraticInOut,
-                                        Duration::from_millis(500),
-                                        TransformPositionLens {
-                                            start: transform.translation,
-                                            end: Vec3{ x: final_pos.0, y: final_pos.1, z: 0.5},
-                                        },
-                                    );
-                                    let tween_sc = Tween::new(
-                                        EaseFunction::QuadraticInOut,
-                                        Duration::from_millis(500),
-                                        TransformScaleLens {
-                                            start: transform.scale,
-                                            end: Vec3{ x: 1., y: 1., z: 0.},
-                                        },
-                                    );
-                                    let track = Tracks::new([tween_tr, tween_sc]);
-                                    anim.set_tweenable(track);
+                    Function::RedirectSouls { dam, dest } => {
+                        let new_info = if let Ok((species, _breath, ax, virtues, pos, is_player)) = creatures.p0().get(dest) {
+                            CasterInfo::new(dest, pos, species, virtues, &ax.status, is_player)
+                        } else { break 'resolve Err(AxiomError::MissingCreature(dest)) };
+                        world_map.targeted_axioms.push((entity, Function::FlatStealSouls { dam }, new_info));
+                    },
+                    Function::Dash { dx, dy } => {
+                        let dest = (dx, dy);
+                        let mut line = bresenham_line(pos.x as i32, pos.y as i32, pos.x as i32 + dest.0, pos.y as i32 + dest.1);
+                        line.remove(0); // remove the origin point
+                        let (mut fx, mut fy) = (pos.x as isize, pos.y as isize);
+                        for (nx, ny) in line {
+                            let (x, y) = if !is_in_bounds(nx, ny) {
+                                if !(ny >= 0 && ny < WORLD_HEIGHT as i32) {
+                                    continue;
                                 }
-                                else{ panic!("A soul in the draw pile has no UIElement component!")};
+                                else if nx < 0 {
+                                    (45+nx, ny)
+                                } else {
+                                    (nx % 45, ny % 45)
+                                }
+                            } else {(nx,ny)};
+                            if world_map.entities[xy_idx(x as usize, y as usize)].is_some() { // TODO replace this with world wrap
+                                // TODO Raise a collision event here
+                                let collider = world_map.entities[xy_idx(x as usize, y as usize)].unwrap(); // Same tile as the check above, wrapped around the world
+                                world_map.targeted_axioms.push((entity, Function::Collide { with: collider }, info.clone()));
+                                break;
                             }
+                            else {(fx, fy) = (nx as isize, ny as isize)}
                         }
-                        let mut harmony_deck = breath.discard.clone();
-                        if found_convert.is_some(){
-                            let found_convert = found_convert.unwrap();
-                            if let Some(index) = harmony_deck[match_soul_with_display_index(&found_convert.1)].iter().position(|entity| *entity == *found_convert.0) {
-                                harmony_deck[match_soul_with_display_index(&found_convert.1)].remove(index);
-                            }
-                            harmony_deck[0].push(*found_convert.0);
+                        world_map.targeted_axioms.push((entity, Function::Teleport { x: fx, y: fy }, info.clone()));
+                    },
+                    Function::FlatMomentumDash { dist } => {
+                        let dest = (dist as i32 * info.momentum.0, dist as i32 * info.momentum.1);
+                        world_map.targeted_axioms.push((entity, Function::Dash { dx: dest.0, dy: dest.1 }, info.clone()));
+                    },
+                    Function::MomentumSlamDash { dist } => {
+                        world_map.targeted_axioms.push((entity, Function::Sequence(vec![Function::MeleeSlam { dist }, Function::FlatMomentumDash { dist }]), info.clone()));
+                    },
+                    Function::Sequence(functions) => {
+                        for function in functions {
+                            world_map.targeted_axioms.push((entity, function, info.clone()));
+                        }
+                    },
+                    Function::IfTargetHas { effect, then, otherwise } => {
+                        let found = virtues.get(&effect).map_or_else(|| effects.status.iter().any(|eff| discriminant(&eff.effect_type) == discriminant(&effect) && eff.stacks > 0), |stacks| stacks > 0);
+                        world_map.targeted_axioms.push((entity, if found { *then } else { *otherwise }, info.clone()));
+                    },
+                    Function::Repeat { times, of } => {
+                        for _ in 0..times {
+                            world_map.targeted_axioms.push((entity, (*of).clone(), info.clone()));
                         }
-                        breath.pile = harmony_deck;
-                        for vec in breath.discard.iter_mut() {
-                            vec.clear();
+                    },
+                    Function::ScaleByTrigger { of } => { // Not fired by a trigger, so there is nothing to scale by.
+                        world_map.targeted_axioms.push((entity, *of, info.clone()));
+                    },
+                    Function::ScaleBy { virtue, of } => {
+                        world_map.targeted_axioms.push((entity, scale_function(*of, info.stacks(&virtue)), info.clone()));
+                    },
+                    Function::MeleeSlam { dist } => {
+                        let Ok(coll_pos) = creatures.p1().get(info.entity).map(|e| (e.x, e.y)) else { break 'resolve Err(AxiomError::MissingCreature(info.entity)) };
+                        info.pos = coll_pos;
+                        let targets = grab_coords_from_form(&world_map.entities, Form::MomentumTouch, info.clone(), &census);
+                        for target in targets.entities {
+                            world_map.targeted_axioms.push((target, Function::FlatMomentumDash { dist }, info.clone()));
                         }
-                        for (i, vec) in breath.pile.iter().enumerate() { if !vec.is_empty() {possible_indices.push(i)}}
                     }
-                    let index = possible_indices.choose(&mut rng);
-                    let replacement = breath.pile[index.unwrap().to_owned()].pop();
-                    match replacement { // Replace the used soul.
-                        Some(new_soul) => {
-                            if breath.held.len() < 4 {
-                                breath.held.push(new_soul);
-                            }
-                            else { breath.held[slot] = new_soul; }
-    
-                            let slot_coords_ui = [
-                                ((3.*PI/4.).cos() * 1.5 +ui_center.x, (3.*PI/4.).sin() * 1.5 +ui_center.y),
-                                ((1.*PI/4.).cos() * 1.5 +ui_center.x, (1.*PI/4.).sin() * 1.5 +ui_center.y),
-                                ((5.*PI/4.).cos() * 1.5 +ui_center.x, (5.*PI/4.).sin() * 1.5 +ui_center.y),
-                                ((7.*PI/4.).cos() * 1.5 +ui_center.x, (7.*PI/4.).sin() * 1.5 +ui_center.y)
-                            ];
-                            if let Ok((mut anim, transform, _sprite, _soul_id), ) = souls.get_mut(new_soul) { 
-                                let tween_tr = Tween::new(
-                                    EaseFunction::QuadraticInOut,
-                                    Duration::from_millis(500),
-                                    TransformPositionLens {
-                                        start: transform.translation,
-                                        end: Vec3{ x: slot_coords_ui[slot].0, y: slot_coords_ui[slot].1, z: 0.5},
-                                    },
-                                );
-                                let tween_sc = Tween::new(
-                                    EaseFunction::QuadraticInOut,
-                                    Duration::from_millis(500),
-                                    TransformScaleLens {
-                                        start: transform.scale,
-                                        end: Vec3{ x: 2., y: 2., z: 0.},
-                                    },
-                                );
-                                let track = Tracks::new([tween_tr, tween_sc]);
-                                anim.set_tweenable(track);
+                    Function::MomentumReverseDash => {
+                        let dist = info.virtues.grace;
+                        let dest = (dist as i32 * -info.momentum.0, dist as i32 * -info.momentum.1);
+                        world_map.targeted_axioms.push((entity, Function::Dash { dx: dest.0, dy: dest.1 }, info.clone()));
+                    },
+                    Function::DiscardSoul { soul, slot } => {
+                        let index = if breath.held.get(slot) == Some(&soul) { Some(slot) } else { breath.held.iter().position(|held| *held == soul) };
+                        let Some(index) = index else { break 'resolve Ok(AxiomOutcome::Fizzled) }; // Stolen before it could be discarded.
+                        breath.held.remove(index);
+                        breath.discard[match_soul_with_display_index(&soul)].push(soul);
+                        if breath.draw == DrawRule::OnCast { // Replace the used soul.
+                            world_map.targeted_axioms.push((entity, Function::DrawSoul { slot }, info.clone()));
+                        }
+                    },
+                    Function::DrawSoul { slot } => {
+                        if !breath.has_room() {
+                            break 'resolve Ok(AxiomOutcome::Blocked);
+                        }
+                        if breath.pile_is_empty() {
+                            if breath.discard_is_empty() {
+                                break 'resolve Err(AxiomError::NoSoulsLeft(entity));
                             }
-                        },
-                        None => panic!("The chosen Soul category had nothing left!")
+                            // Reshuffle if no souls are left, then try again.
+                            world_map.targeted_axioms.push((entity, Function::ShuffleSouls, info.clone()));
+                            world_map.targeted_axioms.push((entity, Function::DrawSoul { slot }, info.clone()));
+                            break 'resolve Ok(AxiomOutcome::Blocked);
+                        }
+                        let possible_indices: Vec<usize> = (0..breath.pile.len()).filter(|index| !breath.pile[*index].is_empty()).collect();
+                        let index = *possible_indices.choose(&mut rng.souls).unwrap();
+                        let new_soul = breath.pile[index].pop().unwrap();
+                        let slot = slot.min(breath.held.len());
+                        breath.held.insert(slot, new_soul);
+                    },
+                    Function::ShuffleSouls => {
+                        let mut harmony_deck = mem::replace(&mut breath.discard, vec![Vec::new(); 5]);
+                        // Once there are Serene souls in the discard, each shuffle harmonizes one more.
+                        let harmonized = [Soul::Vile,Soul::Feral,Soul::Saintly,Soul::Ordered].choose(&mut rng.souls).unwrap(); // TODO it should target slots with remaining souls only?
+                        if !harmony_deck[0].is_empty() && harmony_deck[match_soul_with_display_index(harmonized)].pop().is_some() {
+                            harmony_deck[0].push(Soul::Serene);
+                        }
+                        for (pile, caste) in breath.pile.iter_mut().zip(harmony_deck.iter_mut()) {
+                            pile.append(caste);
+                        }
+                    },
+                    Function::RefillHand => { // No more draws than there are souls to draw.
+                        let available = breath.pile.iter().chain(breath.discard.iter()).map(|caste| caste.len()).sum::<usize>();
+                        for slot in breath.held.len()..breath.held.len() + breath.missing().min(available) {
+                            world_map.targeted_axioms.push((entity, Function::DrawSoul { slot }, info.clone()));
+                        }
+                    },
+                    Function::ExhaustSouls { num } => {
+                        let mut exhausted = select_random_souls(&mut breath.discard, num, &mut rng.combat);
+                        breath.exhaust.append(&mut exhausted);
+                    },
+                    Function::SummonCreature { species: _ } => { // Aimed at a creature, so the tile is taken.
+                        break 'resolve Ok(AxiomOutcome::Blocked);
                     }
-                },
-                _ => panic!("Unknown Function used!")
-            };
-            //let new_stats = &effects.status.clone();
-
+                    _ => break 'resolve Err(AxiomError::UnknownFunction(function.clone()))
+                };
+                //let new_stats = &effects.status.clone();
+                Ok(AxiomOutcome::Applied)
+            } else { Err(AxiomError::MissingCreature(entity)) }
+        };
+        let mut queued: Vec<_> = world_map.targeted_axioms.take_pushed().into_iter().map(|(target, function)| (AxiomTarget::Creature(target), function)).collect();
+        queued.extend(world_map.floor_axioms.take_pushed().into_iter().map(|(coords, function)| (AxiomTarget::Tile(coords), function)));
+        let (outcome, error) = settle_outcome(&function, result);
+        axiom_events.send(AxiomEvent { caster: info.entity, target: AxiomTarget::Creature(entity), function, outcome, error, queued });
+    }
+    if anti_infinite_loop > AXIOM_BUDGET {
+        // The chain feeds itself. Drop whatever is left of it instead of hanging or crashing the game.
+        let cycle = find_axiom_cycle(&resolved).join(" -> ");
+        world_map.targeted_axioms.clear();
+        world_map.floor_axioms.clear();
+        notices.send(LogNotice(format!("[r]The Axioms collapse under their own weight![w] The chain was cut short after {AXIOM_BUDGET} Functions: {cycle}")));
+        warn!("Axiom loop cut short: {cycle}");
+        match OpenOptions::new().create(true).append(true).open(AXIOM_LOOP_LOG) {
+            Ok(mut file) => { let _ = writeln!(file, "{cycle}\n    last resolved: {}", resolved[resolved.len().saturating_sub(40)..].join(", ")); },
+            Err(err) => warn!("Could not write {AXIOM_LOOP_LOG}: {err}"),
         }
     }
     world_map.anim_queue.reverse(); // I will probably forget about this and rage later
@@ -1158,9 +1195,68 @@ fn dispense_functions(
     next_state.set(TurnState::UnpackingAnimation);
 }
 
+// A Function that could not be resolved fizzles, and says why in the console.
+fn settle_outcome(
+    function: &Function,
+    result: Result<AxiomOutcome, AxiomError>,
+) -> (AxiomOutcome, Option<AxiomError>) {
+    match result {
+        Ok(outcome) => (outcome, None),
+        Err(error) => {
+            warn!("{function:?} fizzled: {error}");
+            (AxiomOutcome::Fizzled, Some(error))
+        }
+    }
+}
+
+// How many Functions can resolve in a single turn before the chain is considered to be looping.
+pub const AXIOM_BUDGET: usize = 500;
+pub const AXIOM_LOOP_LOG: &str = "axiom_loops.log";
+
+pub fn describe_function(
+    function: &Function,
+) -> String {
+    match function {
+        Function::TriggerEffect { trig, .. } => format!("TriggerEffect({trig:?})"),
+        _ => {
+            let debug = format!("{function:?}");
+            debug.split([' ', '{', '(']).next().unwrap_or_default().to_owned()
+        }
+    }
+}
+
+// Find the shortest sequence of Functions that keeps repeating at the end of the chain, e.g. ["A", "B", "A"].
+// If there is none, the last few Functions are returned instead.
+fn find_axiom_cycle(
+    resolved: &[String],
+) -> Vec<String> {
+    for period in 1..=(resolved.len()/2).min(64) {
+        let end = resolved.len();
+        if resolved[end-period..] == resolved[end-2*period..end-period] {
+            let mut cycle = resolved[end-period..].to_vec();
+            cycle.push(cycle[0].clone());
+            return cycle;
+        }
+    }
+    resolved[resolved.len().saturating_sub(10)..].to_vec()
+}
+
+pub fn log_axiom_events(
+    mut events: EventReader<AxiomEvent>,
+    mut log: ResMut<AxiomLog>,
+){
+    for event in events.read() {
+        log.events.push_back(event.clone());
+        if log.events.len() > AXIOM_LOG_LENGTH {
+            // Fizzles on bare floor make way first, so a wide Form doesn't push out the events that matter.
+            let oldest = log.events.iter().position(|event| matches!(event.target, AxiomTarget::Tile(_)) && event.outcome == AxiomOutcome::Fizzled);
+            log.events.remove(oldest.unwrap_or(0));
+        }
+    }
+}
+
 fn unpack_animations(
     mut creatures: Query<(&SoulBreath, &mut Transform, &mut TextureAtlasSprite, &mut Animator<Transform>, &Position, Has<RealityAnchor>), With<Position>>,
-    mut souls: Query<(&mut Animator<Transform>, &mut Visibility), (With<Soul>,Without<Position>)>,
     player: Query<&Position>,
     new_player: Query<Entity, With<RealityAnchor>>,
     mut next_state: ResMut<NextState<TurnState>>,
@@ -1169,6 +1265,7 @@ fn unpack_animations(
     mut commands: Commands,
     door_anims: Query<Entity, With<DoorAnimation>>,
     texture_atlas_handle: Res<SpriteSheetHandle>,
+    harmony: Res<Harmony>,
 ){
     world_map.animation_timer.tick(time.delta());
     if !world_map.animation_timer.just_finished() {
@@ -1200,7 +1297,7 @@ fn unpack_animations(
                 anim_crea.set_tweenable(tween);
             }
             world_map.animation_timer.set_duration(Duration::from_millis(1));
-            next_state.set(TurnState::AwaitingInput);
+            next_state.set(if harmony.critical { TurnState::Harmonized } else { TurnState::AwaitingInput });
             return;
         }
     };
@@ -1325,18 +1422,9 @@ fn unpack_animations(
             Animation::SoulDrain { source, destination, mut drained } => {
                 let soul = drained.pop();
                 let soul = if let Some(soul) = soul { soul } else { return; };
-                if let Ok((mut anim, mut vis) ) = souls.get_mut(soul) { 
-                    *vis = Visibility::Visible;
-                    let tween = Tween::new(
-                        EaseFunction::QuadraticInOut,
-                        Duration::from_millis(500),
-                        TransformPositionLens {
-                            start: source,
-                            end: destination,
-                        },
-                    );
-                    anim.set_tweenable(tween);
-                }
+                let start = Vec3::new(player_trans.x + (source.0 as f32 -player_pos.0 as f32)/2., player_trans.y + (source.1 as f32 -player_pos.1 as f32)/2., 0.5);
+                let end = Vec3::new(player_trans.x + (destination.0 as f32 -player_pos.0 as f32)/2., player_trans.y + (destination.1 as f32 -player_pos.1 as f32)/2., 0.5);
+                spawn_flying_soul(&mut commands, &texture_atlas_handle, soul, start, end);
                 world_map.anim_queue.push((entity, Animation::SoulDrain { source, destination, drained: drained.clone() }));
                 world_map.animation_timer.set_duration(Duration::from_millis(25));
             },
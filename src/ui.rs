@@ -3,7 +3,7 @@ use std::{f32::consts::PI, time::Duration};
 use bevy::{prelude::*, text::{BreakLineOn, Text2dBounds, TextLayoutInfo}, sprite::Anchor};
 use bevy_tweening::{Tween, EaseFunction, lens::TransformPositionLens, Animator};
 
-use crate::{SpriteSheetHandle, components::{MinimapTile, LogIndex, MomentumMarker, EffectTracker, AxiomEffects, CreatureDescription}, map::{WORLD_HEIGHT, WORLD_WIDTH, WorldMap, xy_idx}, species::{Species, match_species_with_pixel}, TurnState, text::{LORE, split_text}, soul::CurrentEntityInUI, axiom::match_effect_with_sprite};
+use crate::{SpriteSheetHandle, components::{MinimapTile, LogIndex, MomentumMarker, EffectTracker, AxiomEffects, Virtues, CreatureDescription, Faction, Initiative, Position, PreviewMarker, RealityAnchor, SoulBreath, TetherMarker, TurnOrderTracker}, map::{WORLD_HEIGHT, WORLD_WIDTH, WorldMap, xy_idx}, species::{Species, calculate_speed, match_faction_with_index, match_species_with_pixel, match_species_with_sprite}, TurnState, text::{LORE, split_text}, soul::CurrentEntityInUI, axiom::{grab_coords_from_form, match_effect_with_definition, take_census, CasterInfo, EffectType, Form}, turn::predict_turn_order};
 
 pub struct UIPlugin;
 
@@ -11,11 +11,13 @@ impl Plugin for UIPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, (draw_chain_borders, draw_soul_deck, draw_sidebar));
         app.add_systems(PostStartup, draw_minimap);
-        app.add_systems(OnEnter(TurnState::AwaitingInput), update_minimap);
+        app.add_systems(OnEnter(TurnState::AwaitingInput), (update_minimap, update_turn_order, preview_crescent_arcs));
+        app.add_systems(OnExit(TurnState::AwaitingInput), clear_previews);
         app.add_systems(Update, (place_down_text, push_log));
         app.add_systems(Update, update_effect_trackers.run_if(in_state(TurnState::UnpackingAnimation)));
         app.insert_resource(CenterOfWheel{x: 16.5+7.25, y: 2.3+5.});
         app.add_event::<LogMessage>();
+        app.add_event::<LogNotice>();
     }
 }
 
@@ -89,17 +91,53 @@ fn draw_soul_deck(
 
 fn update_effect_trackers(
     curr_ent: Res<CurrentEntityInUI>,
-    effects: Query<&AxiomEffects>,
+    effects: Query<(&AxiomEffects, &Virtues)>,
     mut trackers_icon: Query<(&EffectTracker, &mut TextureAtlasSprite, &mut Visibility), Without<Text>>,
     mut trackers_text: Query<(&EffectTracker, &mut Text, &mut Visibility), Without<TextureAtlasSprite>>,
+    creatures: Query<&Transform, (With<Position>, Without<TetherMarker>)>,
+    tethers: Query<Entity, With<TetherMarker>>,
+    mut commands: Commands,
+    texture_atlas_handle: Res<SpriteSheetHandle>,
 ) {
-    let ax = if let Ok(ax) = effects.get(curr_ent.entity) {ax} else {panic!("No selected entity!")};
-    let effect_list = &ax.status;
+    let (ax, virtues) = if let Ok(ax) = effects.get(curr_ent.entity) {ax} else {panic!("No selected entity!")};
+    let mut effect_list = virtues.as_effects(); // The virtues always come first.
+    effect_list.extend(ax.status.iter().cloned());
+    // Chain the selected creature to everything its Links hit, following them as they move.
+    for tether in tethers.iter() {
+        commands.entity(tether).despawn();
+    }
+    for eff in &effect_list {
+        let EffectType::Link { targets, .. } = &eff.effect_type else { continue };
+        let Ok(holder) = creatures.get(curr_ent.entity) else { continue };
+        for target in targets.iter().filter_map(|target| creatures.get(*target).ok()) {
+            let (start, end) = (holder.translation.truncate(), target.translation.truncate());
+            let links = (start.distance(end) / 0.25) as usize;
+            let angle = (end - start).y.atan2((end - start).x);
+            for i in 1..links {
+                let point = start.lerp(end, i as f32 / links as f32);
+                commands.spawn((SpriteSheetBundle {
+                    texture_atlas: texture_atlas_handle.handle.clone(),
+                    sprite: TextureAtlasSprite{
+                        index : 139,
+                        color: Color::CYAN,
+                        custom_size: Some(Vec2::new(1., 1.)),
+                        ..default()
+                    },
+                    transform: Transform {
+                        translation: point.extend(0.3),
+                        rotation: Quat::from_rotation_z(angle + 3.*PI/2.),
+                        scale: Vec3::new(0.25, 0.25, 1.),
+                    },
+                    ..default()
+                }, TetherMarker));
+            }
+        }
+    }
     for (index, mut sprite, mut vis) in trackers_icon.iter_mut() {
         let corr_effect = effect_list.get(index.tracking_index);
         match corr_effect {
             Some(eff) => {
-                sprite.index = match_effect_with_sprite(&eff.effect_type);
+                sprite.index = match_effect_with_definition(&eff.effect_type).sprite;
                 *vis = Visibility::Visible;
             },
             None => {
@@ -209,6 +247,95 @@ fn draw_sidebar(
             EffectTracker {tracking_index: i},
         ));
     }
+    for i in 0..TURN_ORDER_LENGTH {
+        commands.spawn((UIBundle{
+            sprite_bundle:SpriteSheetBundle {
+                texture_atlas: texture_atlas_handle.handle.clone(),
+                sprite: TextureAtlasSprite{
+                    index : 0,
+                    custom_size: Some(Vec2::new(1., 1.)),
+                    ..default()
+                },
+                transform: Transform {
+                    translation: Vec3::new(0.2 + i as f32*0.55, -4.1, 1.),
+                    scale: Vec3::new(0.5,0.5,1.),
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            name: Name::new("Turn Order Icon")
+        },
+        TurnOrderTracker {index: i}));
+    }
+}
+
+// Show where the player's CrescentArc Axioms would land, as wide as the last swipe made them.
+fn preview_crescent_arcs(
+    player: Query<(Entity, &Position, &Transform, &Species, &AxiomEffects, &Virtues), With<RealityAnchor>>,
+    census: Query<(Entity, &Species, &Faction)>,
+    map: Res<WorldMap>,
+    mut commands: Commands,
+    texture_atlas_handle: Res<SpriteSheetHandle>,
+){
+    let Ok((entity, pos, trans, species, effects, virtues)) = player.get_single() else { return };
+    if !effects.axioms.iter().any(|(form, _)| form == &Form::CrescentArc) { return; }
+    let info = CasterInfo::new(entity, pos, species, virtues, &effects.status, true);
+    let arc = grab_coords_from_form(&map.entities, Form::CrescentArc, info, &take_census(census.iter()));
+    for coords in arc.coords {
+        commands.spawn((SpriteSheetBundle {
+            texture_atlas: texture_atlas_handle.handle.clone(),
+            sprite: TextureAtlasSprite {
+                index: 14,
+                color: Color::rgba(1., 0.6, 0.2, 0.5),
+                custom_size: Some(Vec2::new(1., 1.)),
+                ..default()
+            },
+            transform: Transform {
+                translation: Vec3::new(trans.translation.x + (coords.0 as f32 - pos.x as f32)/2., trans.translation.y + (coords.1 as f32 - pos.y as f32)/2., 0.),
+                scale: Vec3::new(0.5, 0.5, 1.),
+                ..default()
+            },
+            ..default()
+        }, PreviewMarker));
+    }
+}
+
+fn clear_previews(
+    previews: Query<Entity, With<PreviewMarker>>,
+    mut commands: Commands,
+){
+    for preview in previews.iter() {
+        commands.entity(preview).despawn();
+    }
+}
+
+// How many upcoming turns are shown under the status effects.
+pub const TURN_ORDER_LENGTH: usize = 6;
+
+fn update_turn_order(
+    creatures: Query<(Entity, &Initiative, &Species, &Virtues, &SoulBreath, &Faction, Has<RealityAnchor>)>,
+    read_species: Query<&Species>,
+    mut trackers: Query<(&TurnOrderTracker, &mut TextureAtlasSprite, &mut Visibility)>,
+){
+    let mut actors = Vec::new();
+    for (entity, initiative, species, virtues, breath, faction, is_player) in creatures.iter() {
+        if !is_player && (breath.soulless || match_faction_with_index(faction).is_none()) { continue; } // Only those who fight.
+        let actor = (entity, initiative.energy, calculate_speed(species, virtues));
+        if is_player { actors.insert(0, actor); } else { actors.push(actor); }
+    }
+    let order = predict_turn_order(actors, TURN_ORDER_LENGTH);
+    for (tracker, mut sprite, mut vis) in trackers.iter_mut() {
+        match order.get(tracker.index).and_then(|entity| read_species.get(*entity).ok()) {
+            Some(species) => {
+                sprite.index = match_species_with_sprite(species);
+                *vis = Visibility::Visible;
+            },
+            None => {
+                *vis = Visibility::Hidden;
+            }
+        }
+    }
 }
 
 fn update_minimap(
@@ -265,18 +392,23 @@ fn draw_minimap(
 #[derive(Event)]
 pub struct LogMessage(pub usize);
 
+// A message written on the spot by the game itself, rather than picked from the LORE.
+#[derive(Event)]
+pub struct LogNotice(pub String);
+
 fn place_down_text(
     mut commands: Commands, 
     asset_server: Res<AssetServer>,
-    mut events: EventReader<LogMessage>
+    mut events: EventReader<LogMessage>,
+    mut notices: EventReader<LogNotice>,
 ){
-    for event in events.read(){
+    let lore = events.read().map(|event| match LORE.get(event.0) {
+        Some(lore) => lore.to_string(),
+        None => "\"Hi, Onei here. That text index doesn't exist, so here I am instead. Please report this bug.\"".to_owned(),
+    });
+    for chosen_text in lore.chain(notices.read().map(|notice| notice.0.clone())){
         let mut text_sections = Vec::new();
-        let chosen_text = match LORE.get(event.0) {
-            Some(lore) => *lore,
-            None => "\"Hi, Onei here. That text index doesn't exist, so here I am instead. Please report this bug.\"",
-        };
-        let split_text = split_text(chosen_text, &asset_server);
+        let split_text = split_text(&chosen_text, &asset_server);
         for (snippet, style) in split_text {
             text_sections.push(TextSection::new(snippet, style));
         }
@@ -337,11 +469,11 @@ fn push_log(
         }
     }
     for (entity, _entry, mut anim, mut transform, mut num) in new_text.iter_mut(){
-        if newcomer.is_some(){
-            if newcomer.unwrap().0 == entity {continue;}
+        if let Some((newcomer, size)) = newcomer {
+            if newcomer == entity {continue;}
             if anim.tweenable().progress() != 1.0 {transform.translation.y = num.going_to;}
             let initial_pos = Vec3::new(transform.translation.x, transform.translation.y, 0.07);
-            let final_pos = Vec3::new(12.1+7.25, transform.translation.y + 0.2 + newcomer.unwrap().1.y, 0.07);
+            let final_pos = Vec3::new(12.1+7.25, transform.translation.y + 0.2 + size.y, 0.07);
             if final_pos.y > 4. {
                 commands.entity(entity).despawn();
                 continue;
@@ -1,17 +1,19 @@
 use bevy::prelude::*;
 use rand::{Rng, seq::SliceRandom};
 
-use crate::{species::Species, axiom::{Form, Function, tup_i32_to_usize, tup_usize_to_i32}};
+use crate::{rng::GameRng, species::Species, axiom::{Form, Function, tup_i32_to_usize, tup_usize_to_i32}};
 
 #[derive(Component, Clone)]
 pub enum Vault {
     WorldSeed,
     EpicWow,
     Epsilon,
+    #[allow(dead_code)] // No Plane leads here yet.
     Factory,
     EviePlants,
 }
 
+#[allow(dead_code)]
 pub enum Structure {
     Platform,
     Crucible,
@@ -272,9 +274,25 @@ pub fn get_build_sequence( // I am so surprised this worked on the first try. Ru
     vault: Vault,
     corner: (usize, usize)
 ) -> Vec<(Species, (usize, usize))>{
-    let vault_idx = grab_vault(vault);
-    let mut str_seq = VAULTS[vault_idx];
-    let width = str_seq.split('\n').nth(1).unwrap_or(&"").len();
+    parse_vault(VAULTS[grab_vault(vault)], corner)
+}
+
+// Turn a vault string into creatures to place, with its bottom left corner at the given coordinates.
+pub fn parse_vault(
+    vault: &str,
+    corner: (usize, usize)
+) -> Vec<(Species, (usize, usize))>{
+    parse_vault_with_legend(vault, corner, get_species_from_char)
+}
+
+// Same, with another way to read each character.
+pub fn parse_vault_with_legend(
+    vault: &str,
+    corner: (usize, usize),
+    legend: impl Fn(char) -> Species,
+) -> Vec<(Species, (usize, usize))>{
+    let mut str_seq = vault;
+    let width = str_seq.split('\n').nth(1).unwrap_or("").len();
     let height = str_seq.matches("\n").count()-1;
     let binding = str_seq.replace('\n', "");
     str_seq = &binding;
@@ -283,7 +301,7 @@ pub fn get_build_sequence( // I am so surprised this worked on the first try. Ru
     for x in 0..width{
         for y in 0..height{
             let chara = str_seq.as_bytes()[vault_xy_idx(x, height-1-y, width)] as char; // "length-1-y" because this unfortunately needs to be flipped to match the vault strings.
-            let species = get_species_from_char(chara);
+            let species = legend(chara);
             if species == Species::Void{ // Don't place down "floor" creatures.
                 continue;
             }
@@ -293,12 +311,13 @@ pub fn get_build_sequence( // I am so surprised this worked on the first try. Ru
     output
 }
 
+#[allow(dead_code)] // The generators below are not hooked up to any Plane yet.
 pub fn build_spire(
-
+    rng: &mut GameRng,
 ) -> Vec<(Species, (usize, usize))>
 {
+  let rng = &mut rng.map_gen;
   let mut structures = Vec::new();
-  let mut rng = rand::thread_rng();
   let start_point = rng.gen_range(0..10);
   let mut current_center = (Species::Platform, (start_point, 1));
   let mut summit = 1;
@@ -330,43 +349,42 @@ pub fn build_spire(
 
 }
 
-pub fn build_pit() -> Vec<(Species, (usize, usize))> {
-    let zones = select_struct_coords();
-    let mut rng = rand::thread_rng();
+#[allow(dead_code)]
+pub fn build_pit(rng: &mut GameRng) -> Vec<(Species, (usize, usize))> {
+    let zones = select_struct_coords(rng);
+    let rng = &mut rng.map_gen;
     let mut output = Vec::new();
     let mut placed_blocks = Vec::new();
     let structures = [Structure::Crucible];
     for (x,y) in zones {
-        let chosen_struct = structures.choose(&mut rng).unwrap();
-        match chosen_struct {
-            Structure::Crucible => {
-                let num_sides = rng.gen_range(1..=4);
-                let mut len_sides = Vec::new();
-                let mut current_brush = tup_usize_to_i32((x,y));
-                for _i in 0..num_sides {
-                    len_sides.push(rng.gen_range(1..=9));
-                }
-                let mut stretch = [(0,-1),(0,1),(1,0),(-1,0)];
-                stretch.shuffle(&mut rng);
-                for (i, length) in len_sides.iter().enumerate() {
-                    for _j in 0..*length {
-                        if !placed_blocks.contains(&current_brush) {
-                            output.push((Species::Wall, (tup_i32_to_usize(current_brush))));
-                            placed_blocks.push(current_brush);
-                        }
-                        current_brush = (current_brush.0 + stretch[i].0, current_brush.1 + stretch[i].1);
+        let chosen_struct = structures.choose(rng).unwrap();
+        if let Structure::Crucible = chosen_struct {
+            let num_sides = rng.gen_range(1..=4);
+            let mut len_sides = Vec::new();
+            let mut current_brush = tup_usize_to_i32((x,y));
+            for _i in 0..num_sides {
+                len_sides.push(rng.gen_range(1..=9));
+            }
+            let mut stretch = [(0,-1),(0,1),(1,0),(-1,0)];
+            stretch.shuffle(rng);
+            for (i, length) in len_sides.iter().enumerate() {
+                for _j in 0..*length {
+                    if !placed_blocks.contains(&current_brush) {
+                        output.push((Species::Wall, (tup_i32_to_usize(current_brush))));
+                        placed_blocks.push(current_brush);
                     }
+                    current_brush = (current_brush.0 + stretch[i].0, current_brush.1 + stretch[i].1);
                 }
             }
-            _ => ()
         }
     }
     output
 }
 
-pub fn select_struct_coords() -> Vec<(usize,usize)> {
-    let mut rng = rand::thread_rng();
-    let mut tuples = Vec::new();
+#[allow(dead_code)]
+pub fn select_struct_coords(rng: &mut GameRng) -> Vec<(usize,usize)> {
+    let rng = &mut rng.map_gen;
+    let mut tuples: Vec<(i32, i32)> = Vec::new();
  
     // Generate the first tuple
     let first_tuple = (rng.gen_range(10..=80), rng.gen_range(10..=80));
@@ -379,9 +397,9 @@ pub fn select_struct_coords() -> Vec<(usize,usize)> {
  
             let mut is_valid = true;
             for existing_tuple in &tuples {
-                let distance = ((new_tuple.0 as i32 - existing_tuple.0 as i32).abs() as f64)
+                let distance = ((new_tuple.0 - existing_tuple.0).abs() as f64)
                    .powi(2)
-                   + ((new_tuple.1 as i32 - existing_tuple.1 as i32).abs() as f64).powi(2);
+                   + ((new_tuple.1 - existing_tuple.1).abs() as f64).powi(2);
                 if distance <= 9.0 * 9.0 {
                    is_valid = false;
                    break;
@@ -432,7 +450,7 @@ pub fn match_vault_with_spawn_loc(
     }
 }
 
-fn get_species_from_char(
+pub fn get_species_from_char(
     char: char,
 ) -> Species {
     match char{
@@ -495,4 +513,18 @@ pub fn extract_square(vault: Vault, x: usize, y: usize) -> Vec<Vec<Species>> {
      .map(|line| line.chars().collect())
      .collect()
  }
- 
\ No newline at end of file
+ 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_structures_come_from_the_seed() {
+        assert_eq!(build_pit(&mut GameRng::new(3)), build_pit(&mut GameRng::new(3)));
+        assert_eq!(build_spire(&mut GameRng::new(3)), build_spire(&mut GameRng::new(3)));
+        // Only the map generation stream is drawn from.
+        let (mut fresh, mut used) = (GameRng::new(3), GameRng::new(3));
+        build_pit(&mut used);
+        assert_eq!((fresh.combat.gen::<u64>(), fresh.souls.gen::<u64>()), (used.combat.gen::<u64>(), used.souls.gen::<u64>()));
+    }
+}
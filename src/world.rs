@@ -1,17 +1,29 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
 use crate::vaults::Vault;
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Plane {
+    WorldStem,
     Terminal,
     Epsilon,
 }
 
+// The plane the Reality Anchor is currently standing in.
+#[derive(Resource)]
+pub struct CurrentPlane {
+    pub plane: Plane,
+}
+
+#[allow(dead_code)] // Planes are only reached through vaults for now.
 pub const WORLD_ORDER: &[Plane] = &[Plane::Terminal, Plane::Epsilon];
 
 pub fn match_plane_with_vaults(
     plane: Plane
 ) -> Vault {
     match plane {
+        Plane::WorldStem => Vault::EviePlants,
         Plane::Terminal => Vault::EpicWow,
         Plane::Epsilon => Vault::Epsilon,
     }